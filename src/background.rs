@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+use image::{GrayImage, Luma};
+use imageproc::contours::Contour;
+
+const BRIGHTNESS: u8 = 240;
+const DIM: u8 = 3;
+
+/// Marks the bright region connected to the image border, which is most likely
+/// the blank canvas around the subject.
+pub fn background_mask(gray: &GrayImage) -> GrayImage {
+    let (width, height) = gray.dimensions();
+    let mut mask = GrayImage::new(width, height);
+    let mut queue = VecDeque::new();
+
+    let border = (0..width)
+        .flat_map(|x| [(x, 0), (x, height.saturating_sub(1))])
+        .chain((0..height).flat_map(|y| [(0, y), (width.saturating_sub(1), y)]));
+    for (x, y) in border {
+        if x < width && y < height {
+            queue.push_back((x, y));
+        }
+    }
+
+    while let Some((x, y)) = queue.pop_front() {
+        if mask.get_pixel(x, y).0[0] != 0 || gray.get_pixel(x, y).0[0] <= BRIGHTNESS {
+            continue;
+        }
+        mask.put_pixel(x, y, Luma([255]));
+        if x > 0 {
+            queue.push_back((x - 1, y));
+        }
+        if y > 0 {
+            queue.push_back((x, y - 1));
+        }
+        if x + 1 < width {
+            queue.push_back((x + 1, y));
+        }
+        if y + 1 < height {
+            queue.push_back((x, y + 1));
+        }
+    }
+    mask
+}
+
+/// A contour is background when the majority of its points lie in the mask.
+pub fn is_background(mask: &GrayImage, contour: &Contour<i32>) -> bool {
+    let inside = contour
+        .points
+        .iter()
+        .filter(|p| {
+            p.x >= 0
+                && p.y >= 0
+                && mask
+                    .get_pixel_checked(p.x as u32, p.y as u32)
+                    .is_some_and(|m| m.0[0] != 0)
+        })
+        .count();
+    inside * 2 > contour.points.len()
+}
+
+pub fn dim_contour(image: &mut GrayImage, contour: &Contour<i32>) {
+    for p in &contour.points {
+        if p.x < 0 || p.y < 0 {
+            continue;
+        }
+        if let Some(pixel) = image.get_pixel_mut_checked(p.x as u32, p.y as u32) {
+            pixel.0[0] = pixel.0[0].min(u8::MAX / DIM);
+        }
+    }
+}
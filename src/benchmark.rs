@@ -0,0 +1,64 @@
+use std::{f32::consts::TAU, thread, time::Duration};
+
+use enigo::{Enigo, Mouse, Settings};
+use imageproc::point::Point;
+
+use crate::ui::{cursor_pos, State, STATE};
+
+const CIRCLE_POINTS: usize = 1000;
+const CIRCLE_RADIUS: f32 = 100.0;
+const SLOWEST_US: u64 = 1000;
+const FASTEST_US: u64 = 10;
+const STEP_US: u64 = 50;
+/// Allowed share of points the cursor missed before a speed counts as
+/// unreliable.
+const MAX_MISSES: f32 = 0.01;
+const TOLERANCE: i32 = 2;
+
+/// Traces a circle around `center` at ever shorter per-point delays and
+/// returns the shortest one where the cursor still lands where it was sent.
+/// The button stays up, so nothing gets drawn.
+pub fn find_min_delay(center: Point<i32>) -> Option<u64> {
+    let mut enigo = Enigo::new(&Settings::default()).ok()?;
+    let delays = (0..)
+        .map(|step| SLOWEST_US.saturating_sub(step * STEP_US))
+        .take_while(|delay| *delay > 0)
+        .chain([FASTEST_US]);
+
+    let mut best = None;
+    for delay in delays {
+        if let State::Stop = STATE.load() {
+            break;
+        }
+        if !trace_circle(&mut enigo, center, Duration::from_micros(delay)) {
+            break;
+        }
+        best = Some(delay);
+    }
+    best
+}
+
+fn trace_circle(enigo: &mut Enigo, center: Point<i32>, delay: Duration) -> bool {
+    let mut misses = 0;
+    for index in 0..CIRCLE_POINTS {
+        let angle = index as f32 / CIRCLE_POINTS as f32 * TAU;
+        let target = Point::new(
+            center.x + (angle.cos() * CIRCLE_RADIUS).round() as i32,
+            center.y + (angle.sin() * CIRCLE_RADIUS).round() as i32,
+        );
+        if enigo
+            .move_mouse(target.x, target.y, enigo::Coordinate::Abs)
+            .is_err()
+        {
+            return false;
+        }
+        thread::sleep(delay);
+        let hit = cursor_pos().is_some_and(|actual| {
+            (actual.x - target.x).abs() <= TOLERANCE && (actual.y - target.y).abs() <= TOLERANCE
+        });
+        if !hit {
+            misses += 1;
+        }
+    }
+    (misses as f32 / CIRCLE_POINTS as f32) <= MAX_MISSES
+}
@@ -0,0 +1,82 @@
+use image::{GrayImage, Luma};
+use imageproc::filter::gaussian_blur_f32;
+
+/// Half-width of the window the local variance is measured over.
+const VARIANCE_RADIUS: u32 = 3;
+/// Blur strengths precomputed between no blur and `base_sigma`.
+const SIGMA_STEPS: usize = 6;
+
+/// Blurs flat regions more than detailed ones:
+/// `sigma = base_sigma / (1 + variance)`, with the local variance scaled by
+/// the image's mean variance. The blur is precomputed at a few fixed sigmas
+/// and each pixel blends the two nearest.
+pub fn adaptive_blur(gray: &GrayImage, base_sigma: f32) -> GrayImage {
+    if base_sigma <= 0.0 {
+        return gray.clone();
+    }
+    let variance = local_variance(gray);
+    let mean = variance.iter().sum::<f32>() / variance.len().max(1) as f32;
+    let mean = mean.max(f32::EPSILON);
+
+    let sigmas: Vec<f32> = (0..SIGMA_STEPS)
+        .map(|i| base_sigma * i as f32 / (SIGMA_STEPS - 1) as f32)
+        .collect();
+    let blurred: Vec<GrayImage> = sigmas
+        .iter()
+        .map(|&sigma| {
+            if sigma > 0.0 {
+                gaussian_blur_f32(gray, sigma)
+            } else {
+                gray.clone()
+            }
+        })
+        .collect();
+
+    let step = base_sigma / (SIGMA_STEPS - 1) as f32;
+    GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+        let v = variance[(y * gray.width() + x) as usize] / mean;
+        let position = (base_sigma / (1.0 + v) / step).min((SIGMA_STEPS - 1) as f32);
+        let low = position.floor() as usize;
+        let high = (low + 1).min(SIGMA_STEPS - 1);
+        let t = position - low as f32;
+        let a = blurred[low].get_pixel(x, y).0[0] as f32;
+        let b = blurred[high].get_pixel(x, y).0[0] as f32;
+        Luma([(a + (b - a) * t).round() as u8])
+    })
+}
+
+/// Variance of the brightness in a square window around each pixel, from
+/// summed-area tables.
+fn local_variance(gray: &GrayImage) -> Vec<f32> {
+    let (width, height) = gray.dimensions();
+    let stride = width as usize + 1;
+    let mut sum = vec![0.0f64; stride * (height as usize + 1)];
+    let mut squares = sum.clone();
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let v = gray.get_pixel(x as u32, y as u32).0[0] as f64;
+            let i = (y + 1) * stride + x + 1;
+            sum[i] = v + sum[i - 1] + sum[i - stride] - sum[i - stride - 1];
+            squares[i] = v * v + squares[i - 1] + squares[i - stride] - squares[i - stride - 1];
+        }
+    }
+    let area = |table: &[f64], x0: usize, y0: usize, x1: usize, y1: usize| {
+        table[y1 * stride + x1] - table[y0 * stride + x1] - table[y1 * stride + x0]
+            + table[y0 * stride + x0]
+    };
+
+    let mut variance = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let x0 = x.saturating_sub(VARIANCE_RADIUS) as usize;
+            let y0 = y.saturating_sub(VARIANCE_RADIUS) as usize;
+            let x1 = (x + VARIANCE_RADIUS + 1).min(width) as usize;
+            let y1 = (y + VARIANCE_RADIUS + 1).min(height) as usize;
+            let n = ((x1 - x0) * (y1 - y0)) as f64;
+            let mean = area(&sum, x0, y0, x1, y1) / n;
+            let mean_square = area(&squares, x0, y0, x1, y1) / n;
+            variance.push((mean_square - mean * mean).max(0.0) as f32);
+        }
+    }
+    variance
+}
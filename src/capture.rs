@@ -0,0 +1,227 @@
+use std::{collections::VecDeque, mem::size_of};
+
+use image::RgbaImage;
+use imageproc::point::Point;
+use windows::Win32::{
+    Foundation::HWND,
+    Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+        GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        SRCCOPY,
+    },
+};
+
+#[cfg(not(target_os = "windows"))]
+compile_error!("screen capture goes through GDI and is only available on Windows");
+
+const WHITE: u8 = 230;
+const DETECT_SCALE: u32 = 4;
+/// Share of the area left between the edge and the corner probes.
+const PROBE_INSET: f32 = 0.1;
+/// Channel difference that counts a pixel as part of a test mark.
+const MARK_DIFFERENCE: i16 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScreenRect {
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.x + self.width as i32 / 2,
+            self.y + self.height as i32 / 2,
+        )
+    }
+}
+
+/// Copies a region of the desktop through GDI.
+pub fn capture_screen(rect: ScreenRect) -> Option<RgbaImage> {
+    if rect.width == 0 || rect.height == 0 {
+        return None;
+    }
+    let (width, height) = (rect.width as i32, rect.height as i32);
+    unsafe {
+        let screen = GetDC(HWND::default());
+        let memory = CreateCompatibleDC(screen);
+        let bitmap = CreateCompatibleBitmap(screen, width, height);
+        let previous = SelectObject(memory, bitmap);
+
+        let copied = BitBlt(memory, 0, 0, width, height, screen, rect.x, rect.y, SRCCOPY);
+
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative height asks for top-down rows.
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut buf = vec![0u8; (rect.width * rect.height * 4) as usize];
+        let lines = GetDIBits(
+            memory,
+            bitmap,
+            0,
+            rect.height,
+            Some(buf.as_mut_ptr().cast()),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+
+        SelectObject(memory, previous);
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(memory);
+        ReleaseDC(HWND::default(), screen);
+
+        if copied.is_err() || lines == 0 {
+            return None;
+        }
+        // GDI hands back BGRA.
+        buf.chunks_exact_mut(4).for_each(|px| {
+            px.swap(0, 2);
+            px[3] = 255;
+        });
+        RgbaImage::from_raw(rect.width, rect.height, buf)
+    }
+}
+
+/// Finds the bounding box of the largest connected white-ish region, which on
+/// a typical drawing app screenshot is the blank canvas.
+pub fn detect_canvas(screen: &RgbaImage) -> Option<ScreenRect> {
+    let (width, height) = (
+        screen.width() / DETECT_SCALE,
+        screen.height() / DETECT_SCALE,
+    );
+    let white: Vec<bool> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let px = screen.get_pixel(x * DETECT_SCALE, y * DETECT_SCALE);
+            px.0[..3].iter().all(|c| *c >= WHITE)
+        })
+        .collect();
+
+    let mut seen = vec![false; white.len()];
+    let mut best: Option<(usize, ScreenRect)> = None;
+    let mut queue = VecDeque::new();
+    for start in 0..white.len() {
+        if !white[start] || seen[start] {
+            continue;
+        }
+        seen[start] = true;
+        queue.push_back(start);
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+        let mut size = 0;
+        while let Some(index) = queue.pop_front() {
+            let (x, y) = (index as u32 % width, index as u32 / width);
+            size += 1;
+            (min_x, min_y) = (min_x.min(x), min_y.min(y));
+            (max_x, max_y) = (max_x.max(x), max_y.max(y));
+            let neighbours = [
+                (x > 0).then(|| index - 1),
+                (x + 1 < width).then(|| index + 1),
+                (y > 0).then(|| index - width as usize),
+                (y + 1 < height).then(|| index + width as usize),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if white[next] && !seen[next] {
+                    seen[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        if best.is_none_or(|(best, _)| size > best) {
+            let rect = ScreenRect {
+                x: (min_x * DETECT_SCALE) as i32,
+                y: (min_y * DETECT_SCALE) as i32,
+                width: (max_x - min_x + 1) * DETECT_SCALE,
+                height: (max_y - min_y + 1) * DETECT_SCALE,
+            };
+            best = Some((size, rect));
+        }
+    }
+    best.map(|(_, rect)| rect)
+}
+
+/// Where test marks go: the four corners of `area`, pulled in by
+/// `PROBE_INSET`, then its center.
+pub fn probe_points(area: ScreenRect) -> [Point<i32>; 5] {
+    let inset_x = (area.width as f32 * PROBE_INSET) as i32;
+    let inset_y = (area.height as f32 * PROBE_INSET) as i32;
+    let (left, top) = (area.x + inset_x, area.y + inset_y);
+    let right = area.x + area.width as i32 - inset_x;
+    let bottom = area.y + area.height as i32 - inset_y;
+    let (x, y) = area.center();
+    [
+        Point::new(left, top),
+        Point::new(right, top),
+        Point::new(left, bottom),
+        Point::new(right, bottom),
+        Point::new(x, y),
+    ]
+}
+
+/// Center of the pixels that changed between two captures of the same region.
+pub fn find_mark(before: &RgbaImage, after: &RgbaImage) -> Option<(f32, f32)> {
+    if before.dimensions() != after.dimensions() {
+        return None;
+    }
+    let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0);
+    for (x, y, px) in after.enumerate_pixels() {
+        let old = before.get_pixel(x, y);
+        let changed = (0..3).any(|c| (px.0[c] as i16 - old.0[c] as i16).abs() > MARK_DIFFERENCE);
+        if changed {
+            sum_x += x as f32;
+            sum_y += y as f32;
+            count += 1;
+        }
+    }
+    (count > 0).then(|| (sum_x / count as f32, sum_y / count as f32))
+}
+
+/// The canvas the corner marks were placed in and how far injected movement
+/// stretches on screen, from the marks left at `probe_points`. The rect is
+/// moved back by the center mark's error, so strokes aimed into it land on
+/// the canvas. Every probe needs its mark.
+pub fn canvas_from_marks(
+    probes: &[Point<i32>; 5],
+    marks: &[Option<Point<i32>>; 5],
+) -> Option<(ScreenRect, f64)> {
+    let marks: Vec<Point<i32>> = marks.iter().copied().collect::<Option<_>>()?;
+    let span = |points: &[Point<i32>]| {
+        let (min_x, max_x) = (
+            points.iter().map(|p| p.x).min()?,
+            points.iter().map(|p| p.x).max()?,
+        );
+        let (min_y, max_y) = (
+            points.iter().map(|p| p.y).min()?,
+            points.iter().map(|p| p.y).max()?,
+        );
+        Some((min_x, min_y, max_x - min_x, max_y - min_y))
+    };
+    let (_, _, probe_width, probe_height) = span(&probes[..4])?;
+    let (x, y, width, height) = span(&marks[..4])?;
+    if probe_width <= 0 || probe_height <= 0 || width <= 0 || height <= 0 {
+        return None;
+    }
+    let scale = (width as f64 / probe_width as f64 + height as f64 / probe_height as f64) / 2.0;
+    let (error_x, error_y) = (marks[4].x - probes[4].x, marks[4].y - probes[4].y);
+    // The corner probes sit `PROBE_INSET` in from each edge, so the canvas
+    // is their span grown back out around its center.
+    let grow = 1.0 / (1.0 - 2.0 * PROBE_INSET);
+    let (full_width, full_height) = (width as f32 * grow, height as f32 * grow);
+    let rect = ScreenRect {
+        x: (x as f32 + (width as f32 - full_width) / 2.0).round() as i32 - error_x,
+        y: (y as f32 + (height as f32 - full_height) / 2.0).round() as i32 - error_y,
+        width: full_width.round() as u32,
+        height: full_height.round() as u32,
+    };
+    Some((rect, scale))
+}
@@ -0,0 +1,107 @@
+use imageproc::point::Point;
+use rand::seq::SliceRandom;
+
+#[derive(Debug, Clone, Copy)]
+struct Circle {
+    x: f64,
+    y: f64,
+    radius: f64,
+}
+
+impl Circle {
+    fn contains(&self, (x, y): (f64, f64)) -> bool {
+        (x - self.x).hypot(y - self.y) <= self.radius + 1e-7
+    }
+
+    fn diameter(a: (f64, f64), b: (f64, f64)) -> Self {
+        let (x, y) = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        Self {
+            x,
+            y,
+            radius: (a.0 - x).hypot(a.1 - y),
+        }
+    }
+
+    /// Circumcircle, falling back to the widest pair when the points are in a line.
+    fn through(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Self {
+        let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+        if d.abs() < f64::EPSILON {
+            return [
+                Self::diameter(a, b),
+                Self::diameter(a, c),
+                Self::diameter(b, c),
+            ]
+            .into_iter()
+            .max_by(|l, r| l.radius.total_cmp(&r.radius))
+            .unwrap();
+        }
+        let (sa, sb, sc) = (
+            a.0 * a.0 + a.1 * a.1,
+            b.0 * b.0 + b.1 * b.1,
+            c.0 * c.0 + c.1 * c.1,
+        );
+        let x = (sa * (b.1 - c.1) + sb * (c.1 - a.1) + sc * (a.1 - b.1)) / d;
+        let y = (sa * (c.0 - b.0) + sb * (a.0 - c.0) + sc * (b.0 - a.0)) / d;
+        Self {
+            x,
+            y,
+            radius: (a.0 - x).hypot(a.1 - y),
+        }
+    }
+}
+
+/// Radius of the smallest circle that holds every point (Welzl, in its
+/// iterative randomized-incremental form).
+pub fn min_bounding_radius(points: &[Point<i32>]) -> f64 {
+    let mut points: Vec<(f64, f64)> = points.iter().map(|p| (p.x as f64, p.y as f64)).collect();
+    // Contours come in path order, which is the worst case without a shuffle.
+    points.shuffle(&mut rand::thread_rng());
+    let Some(&first) = points.first() else {
+        return 0.0;
+    };
+
+    let mut circle = Circle {
+        x: first.0,
+        y: first.1,
+        radius: 0.0,
+    };
+    for i in 1..points.len() {
+        if circle.contains(points[i]) {
+            continue;
+        }
+        circle = Circle::diameter(points[0], points[i]);
+        for j in 1..i {
+            if circle.contains(points[j]) {
+                continue;
+            }
+            circle = Circle::diameter(points[i], points[j]);
+            for k in 0..j {
+                if !circle.contains(points[k]) {
+                    circle = Circle::through(points[i], points[j], points[k]);
+                }
+            }
+        }
+    }
+    circle.radius
+}
+
+/// Whether the smallest bounding circle is narrower than `radius`. The
+/// bounding box settles most contours without running the full search.
+pub fn fits_within(points: &[Point<i32>], radius: f64) -> bool {
+    let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) = (
+        points.iter().map(|p| p.x).min(),
+        points.iter().map(|p| p.x).max(),
+        points.iter().map(|p| p.y).min(),
+        points.iter().map(|p| p.y).max(),
+    ) else {
+        return true;
+    };
+    let (width, height) = ((max_x - min_x) as f64, (max_y - min_y) as f64);
+    if width.hypot(height) / 2.0 < radius {
+        return true;
+    }
+    if width.max(height) / 2.0 >= radius {
+        return false;
+    }
+    min_bounding_radius(points) < radius
+}
@@ -0,0 +1,155 @@
+use std::{error::Error, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use windows::Win32::UI::Input::KeyboardAndMouse::{VK_F1, VK_F2, VK_F3, VK_F4};
+
+use crate::{
+    edges_ext::EdgeMethod,
+    input::{Backend, PenUpStyle},
+    order::DrawOrder,
+    ui::{DrawMode, Language, Panel, ResizeFilter, ResizeMode},
+};
+
+/// Drawing parameters and hotkeys kept between runs in
+/// `autodraw/config.toml` under the user's config directory. Machine-specific calibration stays in
+/// [`crate::settings::Settings`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub language: Language,
+    pub edge_method: EdgeMethod,
+    pub filter_type: ResizeFilter,
+    pub resize_mode: ResizeMode,
+    pub canny_value: u32,
+    pub canny_high_value: u32,
+    pub area: u32,
+    pub min_points: usize,
+    /// `None` for no upper limit; TOML can't hold `usize::MAX`.
+    pub max_points: Option<usize>,
+    pub min_area: f64,
+    pub point_delay_us: u64,
+    pub contour_delay_ms: u64,
+    pub pen_travel_speed_px_per_us: f32,
+    pub backend: Backend,
+    pub darkness_threshold: u8,
+    pub brightness_threshold: u8,
+    pub median_radius: u32,
+    pub adaptive_blur_sigma: f32,
+    pub blur_sigma: f32,
+    pub unsharp_mask_sigma: f32,
+    pub unsharp_amount: f32,
+    pub dilate_iters: u32,
+    pub erode_iters: u32,
+    pub always_on_top: bool,
+    pub brightness: i32,
+    pub contrast: f32,
+    pub invert_colors: bool,
+    pub hotkeys: Hotkeys,
+    pub draw_mode: DrawMode,
+    pub pen_up_style: PenUpStyle,
+    pub draw_order: DrawOrder,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Panel::default().config()
+    }
+}
+
+impl Config {
+    /// Missing or unreadable config falls back to the defaults.
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = config_path().ok_or("no config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("autodraw").join("config.toml"))
+}
+
+/// Virtual-key codes of the global drawing hotkeys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub start: u16,
+    pub stop: u16,
+    pub pause: u16,
+    pub skip: u16,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Self {
+            start: VK_F1.0,
+            stop: VK_F2.0,
+            pause: VK_F3.0,
+            skip: VK_F4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    Start,
+    Stop,
+    Pause,
+    Skip,
+}
+
+impl Hotkey {
+    pub const ALL: [Hotkey; 4] = [Hotkey::Start, Hotkey::Stop, Hotkey::Pause, Hotkey::Skip];
+}
+
+impl Hotkeys {
+    pub fn get(&self, hotkey: Hotkey) -> u16 {
+        match hotkey {
+            Hotkey::Start => self.start,
+            Hotkey::Stop => self.stop,
+            Hotkey::Pause => self.pause,
+            Hotkey::Skip => self.skip,
+        }
+    }
+
+    pub fn get_mut(&mut self, hotkey: Hotkey) -> &mut u16 {
+        match hotkey {
+            Hotkey::Start => &mut self.start,
+            Hotkey::Stop => &mut self.stop,
+            Hotkey::Pause => &mut self.pause,
+            Hotkey::Skip => &mut self.skip,
+        }
+    }
+}
+
+/// Display name of a virtual key, e.g. `F1` or `A`.
+pub fn key_name(vk: u16) -> String {
+    match vk {
+        0x30..=0x39 | 0x41..=0x5A => char::from(vk as u8).to_string(),
+        0x70..=0x87 => format!("F{}", vk - 0x70 + 1),
+        0x60..=0x69 => format!("Num{}", vk - 0x60),
+        0x08 => "Backspace".into(),
+        0x09 => "Tab".into(),
+        0x0D => "Enter".into(),
+        0x13 => "Pause".into(),
+        0x1B => "Esc".into(),
+        0x20 => "Space".into(),
+        0x21 => "PageUp".into(),
+        0x22 => "PageDown".into(),
+        0x23 => "End".into(),
+        0x24 => "Home".into(),
+        0x2D => "Insert".into(),
+        0x2E => "Delete".into(),
+        _ => format!("0x{vk:02X}"),
+    }
+}
@@ -0,0 +1,23 @@
+use image::{GrayImage, Luma};
+use imageproc::map::map_colors;
+
+/// How far each pixel's distance from mid-gray is scaled for the contrast
+/// setting. From -1 to 1 it fades from flat gray up to the image as it is;
+/// from 1 to 3 it boosts linearly up to three times the spread.
+pub fn contrast_gain(contrast: f32) -> f32 {
+    if contrast <= 1.0 {
+        (contrast.max(-1.0) + 1.0) / 2.0
+    } else {
+        contrast
+    }
+}
+
+/// Scales the gray levels around mid-gray by [`contrast_gain`], clamping
+/// what falls off either end.
+pub fn adjust_contrast(gray: &GrayImage, contrast: f32) -> GrayImage {
+    let gain = contrast_gain(contrast);
+    map_colors(gray, |p| {
+        let value = (p.0[0] as f32 - 127.5) * gain + 127.5;
+        Luma([value.round().clamp(0.0, 255.0) as u8])
+    })
+}
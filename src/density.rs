@@ -0,0 +1,159 @@
+use image::{Rgba, RgbaImage};
+use imageproc::contours::Contour;
+
+const RADIUS: i32 = 3;
+/// Cold to hot, sampled evenly over the density range.
+const GRADIENT: [[u8; 3]; 4] = [[0, 0, 255], [0, 255, 0], [255, 255, 0], [255, 0, 0]];
+
+pub struct Density {
+    pub heatmap: RgbaImage,
+    pub mean: f32,
+    pub peak: u32,
+}
+
+/// Counts, for every pixel, the contour points within a 3px radius and
+/// colours the counts as a heatmap. Empty pixels stay transparent.
+pub fn density_map(contours: &[Contour<i32>], width: u32, height: u32) -> Density {
+    let mut counts = vec![0u32; (width * height) as usize];
+    for point in contours.iter().flat_map(|contour| contour.points.iter()) {
+        for dy in -RADIUS..=RADIUS {
+            for dx in -RADIUS..=RADIUS {
+                if dx * dx + dy * dy > RADIUS * RADIUS {
+                    continue;
+                }
+                let (x, y) = (point.x + dx, point.y + dy);
+                if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                    continue;
+                }
+                counts[(y as u32 * width + x as u32) as usize] += 1;
+            }
+        }
+    }
+
+    let peak = counts.iter().copied().max().unwrap_or(0);
+    let covered: Vec<u32> = counts.iter().copied().filter(|c| *c > 0).collect();
+    let mean = if covered.is_empty() {
+        0.0
+    } else {
+        covered.iter().sum::<u32>() as f32 / covered.len() as f32
+    };
+
+    let heatmap = RgbaImage::from_fn(width, height, |x, y| {
+        let count = counts[(y * width + x) as usize];
+        if count == 0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        let [r, g, b] = gradient(count as f32 / peak as f32);
+        Rgba([r, g, b, 160])
+    });
+    Density {
+        heatmap,
+        mean,
+        peak,
+    }
+}
+
+fn gradient(t: f32) -> [u8; 3] {
+    let scaled = t.clamp(0.0, 1.0) * (GRADIENT.len() - 1) as f32;
+    let index = (scaled as usize).min(GRADIENT.len() - 2);
+    let f = scaled - index as f32;
+    let (a, b) = (GRADIENT[index], GRADIENT[index + 1]);
+    [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f).round() as u8)
+}
+
+/// Splits the image into a `cells`x`cells` grid and counts the contour points
+/// in each cell, scaled so the busiest cell is 1.0. Row-major.
+pub fn grid_density(contours: &[Contour<i32>], width: u32, height: u32, cells: u32) -> Vec<f32> {
+    let cells = cells.max(1);
+    let mut counts = vec![0u32; (cells * cells) as usize];
+    for point in contours.iter().flat_map(|contour| contour.points.iter()) {
+        if point.x < 0 || point.y < 0 || point.x >= width as i32 || point.y >= height as i32 {
+            continue;
+        }
+        let column = point.x as u32 * cells / width;
+        let row = point.y as u32 * cells / height;
+        counts[(row * cells + column) as usize] += 1;
+    }
+    let peak = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+    counts
+        .into_iter()
+        .map(|count| count as f32 / peak)
+        .collect()
+}
+
+/// Share of inked pixels, 0.0-1.0, that more than one contour passes over
+/// when each is rasterized 1px wide. A contour crossing itself doesn't count.
+pub fn stroke_overlap<'a>(
+    contours: impl IntoIterator<Item = &'a Contour<i32>>,
+    width: u32,
+    height: u32,
+) -> f32 {
+    let mut owner = vec![usize::MAX; (width * height) as usize];
+    let mut shared = vec![false; owner.len()];
+    for (id, contour) in contours.into_iter().enumerate() {
+        let mut mark = |x: i32, y: i32| {
+            if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                return;
+            }
+            let index = (y as u32 * width + x as u32) as usize;
+            match owner[index] {
+                usize::MAX => owner[index] = id,
+                previous if previous != id => shared[index] = true,
+                _ => {}
+            }
+        };
+        match contour.points.as_slice() {
+            [point] => mark(point.x, point.y),
+            points => {
+                for pair in points.windows(2) {
+                    line_pixels(pair[0].x, pair[0].y, pair[1].x, pair[1].y, &mut mark);
+                }
+            }
+        }
+    }
+    let inked = owner.iter().filter(|&&id| id != usize::MAX).count();
+    if inked == 0 {
+        return 0.0;
+    }
+    shared.iter().filter(|&&shared| shared).count() as f32 / inked as f32
+}
+
+/// Bresenham, both ends included.
+pub fn line_pixels(x0: i32, y0: i32, x1: i32, y1: i32, mut plot: impl FnMut(i32, i32)) {
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+    let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+    loop {
+        plot(x, y);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Gini coefficient: 0.0 when every value is equal, towards 1.0 when a few
+/// values hold everything.
+pub fn gini(values: &[f32]) -> f32 {
+    let total: f32 = values.iter().sum();
+    if values.is_empty() || total <= 0.0 {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let n = sorted.len() as f32;
+    let weighted: f32 = sorted
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (i + 1) as f32 * value)
+        .sum();
+    2.0 * weighted / (n * total) - (n + 1.0) / n
+}
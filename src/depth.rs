@@ -0,0 +1,33 @@
+use image::{GrayImage, Luma};
+use imageproc::{
+    filter::gaussian_blur_f32,
+    gradients::{horizontal_sobel, vertical_sobel},
+};
+
+const DEPTH_SIGMA: f32 = 2.0;
+/// Largest response a 3x3 Sobel kernel gives on 8-bit input.
+const SOBEL_MAX: f32 = 4.0 * 255.0;
+
+/// Rough shape-from-shading: reads brightness gradients as surface slope and
+/// keeps how squarely each pixel faces the viewer, 255 facing and 0 edge-on.
+/// Facing surfaces are treated as nearer.
+pub fn depth_map(gray: &GrayImage, depth_scale: f32) -> GrayImage {
+    let smooth = gaussian_blur_f32(gray, DEPTH_SIGMA);
+    let gx = horizontal_sobel(&smooth);
+    let gy = vertical_sobel(&smooth);
+    GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+        let dx = gx.get_pixel(x, y).0[0] as f32 / SOBEL_MAX * depth_scale;
+        let dy = gy.get_pixel(x, y).0[0] as f32 / SOBEL_MAX * depth_scale;
+        let facing = 1.0 / (1.0 + dx * dx + dy * dy).sqrt();
+        Luma([(facing * 255.0).round() as u8])
+    })
+}
+
+pub fn depth_at(map: &GrayImage, x: i32, y: i32) -> f32 {
+    if x < 0 || y < 0 {
+        return 0.0;
+    }
+    map.get_pixel_checked(x as u32, y as u32)
+        .map(|p| p.0[0] as f32 / 255.0)
+        .unwrap_or(0.0)
+}
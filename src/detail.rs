@@ -0,0 +1,25 @@
+use image::{GrayImage, Luma};
+use imageproc::filter::gaussian_blur_f32;
+
+const DETAIL_SIGMA: f32 = 8.0;
+
+pub fn detail_map(edges: &GrayImage) -> GrayImage {
+    let mut map = gaussian_blur_f32(edges, DETAIL_SIGMA);
+    let max = map.pixels().map(|p| p.0[0]).max().unwrap_or(0);
+    if max == 0 {
+        return map;
+    }
+    map.pixels_mut().for_each(|p| {
+        *p = Luma([(p.0[0] as u32 * 255 / max as u32) as u8]);
+    });
+    map
+}
+
+pub fn detail_at(map: &GrayImage, x: i32, y: i32) -> f32 {
+    if x < 0 || y < 0 {
+        return 0.0;
+    }
+    map.get_pixel_checked(x as u32, y as u32)
+        .map(|p| p.0[0] as f32 / 255.0)
+        .unwrap_or(0.0)
+}
@@ -0,0 +1,58 @@
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::POINT,
+        Graphics::Gdi::{
+            EnumDisplaySettingsW, GetMonitorInfoW, MonitorFromPoint, DEVMODEW,
+            ENUM_CURRENT_SETTINGS, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTOPRIMARY,
+        },
+        UI::{
+            HiDpi::{SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2},
+            WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN},
+        },
+    },
+};
+
+/// Has to run before any window is created to take effect.
+pub fn enable_dpi_awareness() {
+    unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2).ok() };
+}
+
+/// Whether the primary screen size Windows reports to us differs from the
+/// monitor's real pixel count, i.e. our coordinates are being scaled.
+pub fn is_scaled() -> bool {
+    let reported = unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) };
+    physical_size().is_some_and(|physical| physical != reported)
+}
+
+/// Current display mode of the primary monitor. Unlike the metrics and the
+/// monitor rectangle, the mode is never scaled.
+fn physical_size() -> Option<(i32, i32)> {
+    unsafe {
+        let monitor = MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY);
+        let mut info = MONITORINFOEXW {
+            monitorInfo: MONITORINFO {
+                cbSize: size_of::<MONITORINFOEXW>() as u32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info.monitorInfo).as_bool() {
+            return None;
+        }
+        let mut mode = DEVMODEW {
+            dmSize: size_of::<DEVMODEW>() as u16,
+            ..Default::default()
+        };
+        if !EnumDisplaySettingsW(
+            PCWSTR(info.szDevice.as_ptr()),
+            ENUM_CURRENT_SETTINGS,
+            &mut mode,
+        )
+        .as_bool()
+        {
+            return None;
+        }
+        Some((mode.dmPelsWidth as i32, mode.dmPelsHeight as i32))
+    }
+}
@@ -0,0 +1,43 @@
+use std::{error::Error, fmt::Write, fs, path::Path};
+
+use imageproc::contours::Contour;
+
+/// Writes the contours to `path` as SVG, one `<polyline>` per contour.
+/// Coordinates are expected relative to the image's top-left corner.
+pub fn export_svg(
+    contours: &[Contour<i32>],
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(path, to_svg(contours, width, height))?;
+    Ok(())
+}
+
+pub fn to_svg(contours: &[Contour<i32>], width: u32, height: u32) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+    );
+    svg.push_str("<g fill=\"none\" stroke=\"black\" stroke-width=\"1\" stroke-linecap=\"round\" stroke-linejoin=\"round\">\n");
+    for contour in contours {
+        let mut points = contour.points.iter().map(|p| format!("{},{}", p.x, p.y));
+        let Some(first) = points.next() else {
+            continue;
+        };
+        let mut list = first.clone();
+        let mut count = 1;
+        for point in points {
+            list.push(' ');
+            list.push_str(&point);
+            count += 1;
+        }
+        // A lone point still needs a segment for the round cap to show as a dot.
+        if count == 1 {
+            list.push(' ');
+            list.push_str(&first);
+        }
+        let _ = writeln!(svg, "<polyline points=\"{list}\"/>");
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
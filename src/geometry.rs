@@ -0,0 +1,15 @@
+use imageproc::point::Point;
+
+/// Area enclosed by the points, taken as a closed polygon (shoelace
+/// formula). Fewer than three points enclose nothing.
+pub fn contour_area(points: &[Point<i32>]) -> f64 {
+    if points.len() < 3 {
+        return 0.0;
+    }
+    let twice: i64 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64)
+        .sum();
+    twice.abs() as f64 / 2.0
+}
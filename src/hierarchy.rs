@@ -0,0 +1,107 @@
+use std::collections::BTreeSet;
+
+use eframe::egui;
+use imageproc::contours::Contour;
+use rust_i18n::t;
+
+/// Node colors by nesting depth, cycling past the last one.
+const DEPTH_COLORS: [egui::Color32; 6] = [
+    egui::Color32::from_rgb(230, 90, 80),
+    egui::Color32::from_rgb(240, 170, 60),
+    egui::Color32::from_rgb(120, 200, 90),
+    egui::Color32::from_rgb(70, 180, 220),
+    egui::Color32::from_rgb(140, 120, 230),
+    egui::Color32::from_rgb(220, 110, 200),
+];
+
+struct Tree<'a> {
+    contours: &'a [Contour<i32>],
+    children: Vec<Vec<usize>>,
+}
+
+/// Contours nested by their `parent` links. Clicking a node selects it, and
+/// unticking it adds it to `excluded`.
+pub fn hierarchy_tree(
+    ui: &mut egui::Ui,
+    contours: &[Contour<i32>],
+    selected: &mut Option<usize>,
+    excluded: &mut BTreeSet<usize>,
+) {
+    let mut children = vec![vec![]; contours.len()];
+    let mut roots = vec![];
+    for (index, contour) in contours.iter().enumerate() {
+        match contour.parent.filter(|&parent| parent < contours.len()) {
+            Some(parent) => children[parent].push(index),
+            None => roots.push(index),
+        }
+    }
+    let tree = Tree { contours, children };
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for root in roots {
+            tree.node(ui, root, 0, selected, excluded);
+        }
+    });
+}
+
+impl Tree<'_> {
+    fn node(
+        &self,
+        ui: &mut egui::Ui,
+        index: usize,
+        depth: usize,
+        selected: &mut Option<usize>,
+        excluded: &mut BTreeSet<usize>,
+    ) {
+        let row = |ui: &mut egui::Ui| {
+            let mut included = !excluded.contains(&index);
+            if ui.checkbox(&mut included, "").changed() {
+                if included {
+                    excluded.remove(&index);
+                } else {
+                    excluded.insert(index);
+                }
+            }
+            let text = egui::RichText::new(self.describe(index))
+                .color(DEPTH_COLORS[depth % DEPTH_COLORS.len()]);
+            if ui
+                .selectable_label(*selected == Some(index), text)
+                .clicked()
+            {
+                *selected = (*selected != Some(index)).then_some(index);
+            }
+        };
+        let children = &self.children[index];
+        if children.is_empty() {
+            ui.horizontal(row);
+            return;
+        }
+        let id = ui.make_persistent_id(("contour", index));
+        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, row)
+            .body(|ui| {
+                for &child in children {
+                    self.node(ui, child, depth + 1, selected, excluded);
+                }
+            });
+    }
+
+    fn describe(&self, index: usize) -> String {
+        let points = &self.contours[index].points;
+        let (min_x, max_x) = points.iter().fold((i32::MAX, i32::MIN), |(lo, hi), p| {
+            (lo.min(p.x), hi.max(p.x))
+        });
+        let (min_y, max_y) = points.iter().fold((i32::MAX, i32::MIN), |(lo, hi), p| {
+            (lo.min(p.y), hi.max(p.y))
+        });
+        t!(
+            "contour_node",
+            index = index,
+            points = points.len(),
+            width = max_x - min_x + 1,
+            height = max_y - min_y + 1,
+            x = min_x,
+            y = min_y
+        )
+        .to_string()
+    }
+}
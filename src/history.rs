@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+
+use rust_i18n::t;
+
+const MAX_DEPTH: usize = 50;
+
+/// A parameter value from before an undoable change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param {
+    Canny(u32),
+    CannyHigh(u32),
+    Area(u32),
+    Simplify(f64),
+}
+
+impl Param {
+    pub fn describe(&self) -> String {
+        match self {
+            Param::Canny(value) => format!("{}{value}", t!("low_threshold")),
+            Param::CannyHigh(value) => format!("{}{value}", t!("high_threshold")),
+            Param::Area(value) => format!("{}{value}%", t!("draw_area")),
+            Param::Simplify(value) => format!("{}{value:.1}", t!("simplify_epsilon")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: VecDeque<Param>,
+    redo: Vec<Param>,
+}
+
+impl History {
+    pub fn record(&mut self, old: Param) {
+        if self.undo.len() == MAX_DEPTH {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(old);
+        self.redo.clear();
+    }
+
+    /// Pops the last change; `current` swaps in the value it replaces so the
+    /// change can be redone.
+    pub fn undo(&mut self, current: impl FnOnce(Param) -> Param) -> Option<Param> {
+        let old = self.undo.pop_back()?;
+        self.redo.push(current(old));
+        Some(old)
+    }
+
+    pub fn redo(&mut self, current: impl FnOnce(Param) -> Param) -> Option<Param> {
+        let new = self.redo.pop()?;
+        self.undo.push_back(current(new));
+        Some(new)
+    }
+
+    pub fn next_undo(&self) -> Option<&Param> {
+        self.undo.back()
+    }
+
+    pub fn next_redo(&self) -> Option<&Param> {
+        self.redo.last()
+    }
+}
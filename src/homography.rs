@@ -0,0 +1,59 @@
+use imageproc::{contours::Contour, point::Point};
+
+/// Projective transform between two planes, with the last coefficient fixed
+/// to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Homography([f64; 8]);
+
+impl Homography {
+    /// Solves for the transform taking each `source` corner onto the matching
+    /// `dest` corner. `None` when three of the points are collinear.
+    pub fn from_points(source: [Point<i32>; 4], dest: [Point<i32>; 4]) -> Option<Self> {
+        let mut rows = [[0.0; 9]; 8];
+        for (i, (s, d)) in source.iter().zip(&dest).enumerate() {
+            let (x, y) = (s.x as f64, s.y as f64);
+            let (u, v) = (d.x as f64, d.y as f64);
+            rows[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+            rows[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+        }
+        solve(rows).map(Homography)
+    }
+
+    pub fn apply(&self, point: &Point<i32>) -> Point<i32> {
+        let [a, b, c, d, e, f, g, h] = self.0;
+        let (x, y) = (point.x as f64, point.y as f64);
+        let w = g * x + h * y + 1.0;
+        Point::new(
+            ((a * x + b * y + c) / w).round() as i32,
+            ((d * x + e * y + f) / w).round() as i32,
+        )
+    }
+
+    pub fn warp(&self, contour: &Contour<i32>) -> Contour<i32> {
+        Contour::new(
+            contour.points.iter().map(|p| self.apply(p)).collect(),
+            contour.border_type,
+            contour.parent,
+        )
+    }
+}
+
+/// Gauss-Jordan elimination with partial pivoting on an augmented 8x9 system.
+fn solve(mut rows: [[f64; 9]; 8]) -> Option<[f64; 8]> {
+    for col in 0..8 {
+        let pivot = (col..8).max_by(|&a, &b| rows[a][col].abs().total_cmp(&rows[b][col].abs()))?;
+        if rows[pivot][col].abs() < 1e-9 {
+            return None;
+        }
+        rows.swap(col, pivot);
+        let lead = rows[col];
+        for (index, row) in rows.iter_mut().enumerate() {
+            if index == col {
+                continue;
+            }
+            let factor = row[col] / lead[col];
+            row.iter_mut().zip(lead).for_each(|(x, l)| *x -= factor * l);
+        }
+    }
+    Some(std::array::from_fn(|i| rows[i][8] / rows[i][i]))
+}
@@ -0,0 +1,349 @@
+use std::{f32::consts::PI, time::Instant};
+
+use enigo::{Enigo, Keyboard, Mouse, Settings};
+use imageproc::point::Point;
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::POINT,
+    UI::{
+        Controls::{
+            CreateSyntheticPointerDevice, DestroySyntheticPointerDevice, HSYNTHETICPOINTERDEVICE,
+            POINTER_FEEDBACK_DEFAULT, POINTER_TYPE_INFO, POINTER_TYPE_INFO_0,
+        },
+        Input::{
+            KeyboardAndMouse::VIRTUAL_KEY,
+            Pointer::{
+                InjectSyntheticPointerInput, POINTER_FLAGS, POINTER_FLAG_DOWN,
+                POINTER_FLAG_INCONTACT, POINTER_FLAG_INRANGE, POINTER_FLAG_UP, POINTER_FLAG_UPDATE,
+                POINTER_INFO, POINTER_PEN_INFO,
+            },
+        },
+        WindowsAndMessaging::{PEN_FLAG_NONE, PEN_MASK_PRESSURE, PT_PEN},
+    },
+};
+
+const MAX_PRESSURE: f32 = 1024.0;
+const BASE_PRESSURE: f32 = 0.8;
+/// Hands can't change pressure faster than this.
+const NOISE_CUTOFF_HZ: f32 = 10.0;
+const TRAVEL_STEP: f32 = 10.0;
+/// Arc height as a share of the travel distance.
+const ARC_BEND: f32 = 0.25;
+const TRAVEL_JITTER: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    Mouse,
+    VirtualTablet,
+}
+
+/// How the cursor travels between strokes while the pen is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PenUpStyle {
+    Direct,
+    Arc,
+    RandomOffset,
+}
+
+impl PenUpStyle {
+    pub const ALL: [PenUpStyle; 3] = [
+        PenUpStyle::Direct,
+        PenUpStyle::Arc,
+        PenUpStyle::RandomOffset,
+    ];
+}
+
+/// Synthetic pen device (Windows 10 1809+), used for apps that only react to
+/// stylus input.
+pub struct Pen {
+    device: HSYNTHETICPOINTERDEVICE,
+    down: bool,
+}
+
+impl Pen {
+    pub fn new() -> windows::core::Result<Self> {
+        let device = unsafe { CreateSyntheticPointerDevice(PT_PEN, 1, POINTER_FEEDBACK_DEFAULT)? };
+        Ok(Self {
+            device,
+            down: false,
+        })
+    }
+
+    fn inject(&self, flags: POINTER_FLAGS, point: &Point<i32>, pressure: f32) -> bool {
+        let info = POINTER_TYPE_INFO {
+            r#type: PT_PEN,
+            Anonymous: POINTER_TYPE_INFO_0 {
+                penInfo: POINTER_PEN_INFO {
+                    pointerInfo: POINTER_INFO {
+                        pointerType: PT_PEN,
+                        pointerFlags: flags,
+                        ptPixelLocation: POINT {
+                            x: point.x,
+                            y: point.y,
+                        },
+                        ..Default::default()
+                    },
+                    penFlags: PEN_FLAG_NONE,
+                    penMask: PEN_MASK_PRESSURE,
+                    pressure: (pressure.clamp(0.0, 1.0) * MAX_PRESSURE) as u32,
+                    ..Default::default()
+                },
+            },
+        };
+        unsafe { InjectSyntheticPointerInput(self.device, &[info]) }.is_ok()
+    }
+
+    pub fn move_to(&mut self, point: &Point<i32>, pressure: f32) -> bool {
+        if self.down {
+            self.inject(
+                POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT | POINTER_FLAG_UPDATE,
+                point,
+                pressure,
+            )
+        } else {
+            self.inject(POINTER_FLAG_INRANGE | POINTER_FLAG_UPDATE, point, 0.0)
+        }
+    }
+
+    pub fn press(&mut self, point: &Point<i32>, pressure: f32) -> bool {
+        self.down = true;
+        self.inject(
+            POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT | POINTER_FLAG_DOWN,
+            point,
+            pressure,
+        )
+    }
+
+    pub fn release(&mut self, point: &Point<i32>) -> bool {
+        if !self.down {
+            return true;
+        }
+        self.down = false;
+        self.inject(POINTER_FLAG_INRANGE | POINTER_FLAG_UP, point, 0.0)
+    }
+}
+
+impl Drop for Pen {
+    fn drop(&mut self) {
+        unsafe { DestroySyntheticPointerDevice(self.device) };
+    }
+}
+
+/// Gaussian pressure jitter, low-pass filtered so it drifts like a hand
+/// instead of flickering from point to point.
+pub struct PressureNoise {
+    amplitude: f32,
+    value: f32,
+    last: Option<Instant>,
+}
+
+impl PressureNoise {
+    pub fn new(amplitude: f32) -> Self {
+        Self {
+            amplitude,
+            value: 0.0,
+            last: None,
+        }
+    }
+
+    pub fn apply(&mut self, pressure: f32) -> f32 {
+        if self.amplitude <= 0.0 {
+            return pressure;
+        }
+        let now = Instant::now();
+        let dt = self
+            .last
+            .map(|last| (now - last).as_secs_f32())
+            .unwrap_or(1.0);
+        self.last = Some(now);
+
+        let rc = 1.0 / (2.0 * PI * NOISE_CUTOFF_HZ);
+        let alpha = (dt / (rc + dt)).max(f32::EPSILON);
+        // Scaled so the filtered signal keeps `amplitude` as its deviation.
+        let sigma = self.amplitude * ((2.0 - alpha) / alpha).sqrt();
+        let sample = Normal::new(0.0, sigma)
+            .map(|normal| normal.sample(&mut rand::thread_rng()))
+            .unwrap_or(0.0);
+        self.value += alpha * (sample - self.value);
+        (pressure + self.value).clamp(0.0, 1.0)
+    }
+}
+
+/// Routes drawing input to the mouse or the synthetic pen. Keyboard input
+/// always goes through enigo.
+pub struct Input {
+    enigo: Enigo,
+    pen: Option<Pen>,
+    noise: PressureNoise,
+    last: Point<i32>,
+    failed: usize,
+}
+
+impl Input {
+    /// Falls back to the mouse when the pen device can't be created.
+    pub fn new(backend: Backend, noise_amplitude: f32) -> Self {
+        let pen = match backend {
+            Backend::Mouse => None,
+            Backend::VirtualTablet => Pen::new().ok(),
+        };
+        Self {
+            enigo: Enigo::new(&Settings::default()).unwrap(),
+            pen,
+            noise: PressureNoise::new(noise_amplitude),
+            last: Point::new(0, 0),
+            failed: 0,
+        }
+    }
+
+    fn track(&mut self, ok: bool) -> bool {
+        if !ok {
+            self.failed += 1;
+        }
+        ok
+    }
+
+    /// Events the system rejected since the last call.
+    pub fn take_failed(&mut self) -> usize {
+        std::mem::take(&mut self.failed)
+    }
+
+    pub fn move_to(&mut self, point: &Point<i32>, pressure: f32) -> bool {
+        self.last = *point;
+        let ok = match &mut self.pen {
+            Some(pen) => pen.move_to(point, self.noise.apply(pressure)),
+            None => self
+                .enigo
+                .move_mouse(point.x, point.y, enigo::Coordinate::Abs)
+                .is_ok(),
+        };
+        self.track(ok)
+    }
+
+    pub fn press(&mut self, pressure: f32) -> bool {
+        let ok = match &mut self.pen {
+            Some(pen) => pen.press(&self.last, self.noise.apply(pressure)),
+            None => self
+                .enigo
+                .button(enigo::Button::Left, enigo::Direction::Press)
+                .is_ok(),
+        };
+        self.track(ok)
+    }
+
+    pub fn release(&mut self) -> bool {
+        let ok = match &mut self.pen {
+            Some(pen) => pen.release(&self.last),
+            None => self
+                .enigo
+                .button(enigo::Button::Left, enigo::Direction::Release)
+                .is_ok(),
+        };
+        self.track(ok)
+    }
+
+    pub fn click(&mut self, pressure: f32) -> bool {
+        match &mut self.pen {
+            Some(_) => self.press(pressure) & self.release(),
+            None => {
+                let ok = self
+                    .enigo
+                    .button(enigo::Button::Left, enigo::Direction::Click)
+                    .is_ok();
+                self.track(ok)
+            }
+        }
+    }
+
+    pub fn position(&self) -> Point<i32> {
+        self.last
+    }
+
+    pub fn key(&mut self, key: VIRTUAL_KEY) -> bool {
+        self.enigo
+            .key(enigo::Key::Other(key.0 as u32), enigo::Direction::Click)
+            .is_ok()
+    }
+
+    /// Holds `keys` down in order, then lets them go in reverse.
+    pub fn chord(&mut self, keys: &[u16]) -> bool {
+        let mut ok = true;
+        for key in keys {
+            ok &= self
+                .enigo
+                .key(enigo::Key::Other(*key as u32), enigo::Direction::Press)
+                .is_ok();
+        }
+        for key in keys.iter().rev() {
+            ok &= self
+                .enigo
+                .key(enigo::Key::Other(*key as u32), enigo::Direction::Release)
+                .is_ok();
+        }
+        ok
+    }
+}
+
+/// In-between cursor positions for a pen-up move from `from` to `to`, both
+/// ends excluded. `Direct` jumps straight there.
+pub fn travel_path(style: PenUpStyle, from: Point<i32>, to: Point<i32>) -> Vec<Point<i32>> {
+    let (dx, dy) = ((to.x - from.x) as f32, (to.y - from.y) as f32);
+    let length = (dx * dx + dy * dy).sqrt();
+    let steps = (length / TRAVEL_STEP) as usize;
+    if style == PenUpStyle::Direct || steps < 2 {
+        return vec![];
+    }
+    // Control point off the midpoint, square to the direction of travel.
+    let control = (
+        from.x as f32 + dx / 2.0 - dy * ARC_BEND,
+        from.y as f32 + dy / 2.0 + dx * ARC_BEND,
+    );
+    let jitter = Normal::new(0.0, TRAVEL_JITTER).ok();
+    let mut rng = rand::thread_rng();
+    (1..steps)
+        .map(|step| {
+            let t = step as f32 / steps as f32;
+            let (x, y) = match style {
+                PenUpStyle::Arc => {
+                    let u = 1.0 - t;
+                    (
+                        u * u * from.x as f32 + 2.0 * u * t * control.0 + t * t * to.x as f32,
+                        u * u * from.y as f32 + 2.0 * u * t * control.1 + t * t * to.y as f32,
+                    )
+                }
+                _ => {
+                    let (jx, jy) = jitter
+                        .map(|normal| (normal.sample(&mut rng), normal.sample(&mut rng)))
+                        .unwrap_or_default();
+                    (from.x as f32 + dx * t + jx, from.y as f32 + dy * t + jy)
+                }
+            };
+            Point::new(x.round() as i32, y.round() as i32)
+        })
+        .collect()
+}
+
+/// Eases off the pressure in tight turns, the way a hand slows down and
+/// lightens at corners.
+pub fn stroke_pressure(points: &[Point<i32>], index: usize) -> f32 {
+    BASE_PRESSURE * (1.0 - 0.5 * turn_angle(points, index) / PI)
+}
+
+/// Direction change at `index` in radians, zero at the ends of the stroke.
+pub fn turn_angle(points: &[Point<i32>], index: usize) -> f32 {
+    let (Some(prev), Some(point), Some(next)) = (
+        index.checked_sub(1).and_then(|i| points.get(i)),
+        points.get(index),
+        points.get(index + 1),
+    ) else {
+        return 0.0;
+    };
+    let a = ((point.y - prev.y) as f32).atan2((point.x - prev.x) as f32);
+    let b = ((next.y - point.y) as f32).atan2((next.x - point.x) as f32);
+    let turn = (b - a).abs();
+    if turn > PI {
+        2.0 * PI - turn
+    } else {
+        turn
+    }
+}
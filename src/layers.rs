@@ -0,0 +1,96 @@
+use image::{imageops::FilterType, DynamicImage, Rgba, RgbaImage};
+
+pub const MAX_LAYERS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 4] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+    ];
+
+    fn blend(self, base: f32, top: f32) -> f32 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Multiply => base * top,
+            BlendMode::Screen => 1.0 - (1.0 - base) * (1.0 - top),
+            BlendMode::Overlay => {
+                if base < 0.5 {
+                    2.0 * base * top
+                } else {
+                    1.0 - 2.0 * (1.0 - base) * (1.0 - top)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub image: RgbaImage,
+    pub blend: BlendMode,
+    pub opacity: f32,
+    pub visible: bool,
+}
+
+impl Layer {
+    pub fn new(name: String, image: DynamicImage) -> Self {
+        Self {
+            name,
+            image: image.to_rgba8(),
+            blend: BlendMode::Normal,
+            opacity: 1.0,
+            visible: true,
+        }
+    }
+}
+
+/// One pass of a multi-pass drawing: the image is traced again at its own
+/// Canny threshold, so bold outlines and fine detail build up on each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerConfig {
+    pub canny_value: u32,
+    /// Shortcut tapped before the pass to set the brush opacity, e.g. `5`
+    /// for 50% in Photoshop.
+    pub opacity_key: Option<String>,
+    /// Wait before the pass, for the previous one to settle.
+    pub blend_delay_ms: u32,
+}
+
+/// Stacks the visible layers bottom to top on a white canvas sized to the
+/// first visible layer.
+pub fn composite(layers: &[Layer]) -> Option<DynamicImage> {
+    let first = layers.iter().find(|layer| layer.visible)?;
+    let (width, height) = first.image.dimensions();
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+
+    for layer in layers.iter().filter(|layer| layer.visible) {
+        let resized;
+        let image = if layer.image.dimensions() == (width, height) {
+            &layer.image
+        } else {
+            resized = image::imageops::resize(&layer.image, width, height, FilterType::Triangle);
+            &resized
+        };
+        for (base, top) in canvas.pixels_mut().zip(image.pixels()) {
+            let alpha = top.0[3] as f32 / 255.0 * layer.opacity;
+            for c in 0..3 {
+                let a = base.0[c] as f32 / 255.0;
+                let b = top.0[c] as f32 / 255.0;
+                let mixed = a * (1.0 - alpha) + layer.blend.blend(a, b) * alpha;
+                base.0[c] = (mixed.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    }
+    Some(DynamicImage::ImageRgba8(canvas))
+}
@@ -3,15 +3,57 @@
 use eframe::{egui::ViewportBuilder, NativeOptions};
 use ui::Panel;
 
+mod background;
+mod benchmark;
+mod blur;
+mod capture;
+mod circle;
+mod config;
+mod contrast;
+mod density;
+mod depth;
+mod detail;
+mod dpi;
+mod edges_ext;
+mod export;
 mod font;
+mod geometry;
+mod hierarchy;
+mod history;
+mod homography;
+mod input;
+mod layers;
+mod order;
+mod palette;
+mod pattern;
+mod profile;
+mod register;
+mod session;
+mod settings;
+mod simplify;
+mod speed;
+mod stats;
+mod trail;
 mod ui;
+mod wave;
+mod window;
 
 rust_i18n::i18n!("i18n");
 
 fn main() {
     rust_i18n::set_locale("zh-CN");
+    let settings = settings::load_settings();
+    if settings.fix_dpi {
+        dpi::enable_dpi_awareness();
+    }
+    let mut viewport = ViewportBuilder::default()
+        .with_inner_size(settings.window_size)
+        .with_transparent(true);
+    if let Some(pos) = settings.window_pos {
+        viewport = viewport.with_position(pos);
+    }
     let options = NativeOptions {
-        viewport: ViewportBuilder::default().with_inner_size([800.0, 800.0]),
+        viewport,
         ..Default::default()
     };
     eframe::run_native(
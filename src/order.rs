@@ -0,0 +1,190 @@
+use std::borrow::Borrow;
+
+use image::{GrayImage, Luma};
+use imageproc::{
+    contours::Contour,
+    gradients::{horizontal_sobel, vertical_sobel},
+    point::Point,
+};
+use serde::{Deserialize, Serialize};
+
+/// Largest gradient magnitude a 3x3 Sobel pair gives on 8-bit input.
+const SOBEL_MAX: f32 = 4.0 * 255.0 * std::f32::consts::SQRT_2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrawOrder {
+    /// As the contours were found.
+    Original,
+    /// Highest `ContourScore` first.
+    Importance,
+    /// By centroid, lowest on screen first.
+    BottomToTop,
+    TopToBottom,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl DrawOrder {
+    pub const ALL: [DrawOrder; 6] = [
+        DrawOrder::Original,
+        DrawOrder::Importance,
+        DrawOrder::BottomToTop,
+        DrawOrder::TopToBottom,
+        DrawOrder::LeftToRight,
+        DrawOrder::RightToLeft,
+    ];
+
+    /// Sorts by centroid for the directional orders; the others are left
+    /// as they are. The sort is stable, so ties keep their order.
+    pub fn sort_by_position(self, contours: &mut [(usize, &Contour<i32>)]) {
+        let key: fn((f32, f32)) -> f32 = match self {
+            DrawOrder::BottomToTop => |(_, y)| -y,
+            DrawOrder::TopToBottom => |(_, y)| y,
+            DrawOrder::LeftToRight => |(x, _)| x,
+            DrawOrder::RightToLeft => |(x, _)| -x,
+            DrawOrder::Original | DrawOrder::Importance => return,
+        };
+        contours.sort_by(|(_, a), (_, b)| key(centroid(a)).total_cmp(&key(centroid(b))));
+    }
+}
+
+fn centroid(contour: &Contour<i32>) -> (f32, f32) {
+    let count = contour.points.len().max(1) as f32;
+    let (x, y) = contour
+        .points
+        .iter()
+        .fold((0.0, 0.0), |(x, y), p| (x + p.x as f32, y + p.y as f32));
+    (x / count, y / count)
+}
+
+/// Weights for how much a contour matters to the picture, so an
+/// interrupted draw still has the important strokes down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContourScore {
+    /// Longer contours score higher.
+    pub length: f32,
+    /// Contours over stronger edges score higher.
+    pub gradient: f32,
+    /// Contours nearer the middle of the image score higher.
+    pub center: f32,
+}
+
+impl Default for ContourScore {
+    fn default() -> Self {
+        Self {
+            length: 1.0,
+            gradient: 1.0,
+            center: 0.3,
+        }
+    }
+}
+
+impl ContourScore {
+    /// Sorts by descending score. `gradient` is a `gradient_magnitude` map of
+    /// the image, whose top-left corner sits at `origin` on screen.
+    pub fn sort<'a>(
+        &self,
+        contours: &mut [(usize, &'a Contour<i32>)],
+        gradient: &GrayImage,
+        origin: (i32, i32),
+    ) {
+        let (width, height) = gradient.dimensions();
+        let middle = (
+            origin.0 as f32 + width as f32 / 2.0,
+            origin.1 as f32 + height as f32 / 2.0,
+        );
+        let reach = (width as f32).hypot(height as f32) / 2.0;
+        let longest = contours
+            .iter()
+            .map(|(_, contour)| contour.points.len())
+            .max()
+            .unwrap_or(1)
+            .max(1) as f32;
+
+        let score = |contour: &Contour<i32>| {
+            let points = contour.points.len().max(1) as f32;
+            let strength = contour
+                .points
+                .iter()
+                .filter_map(|p| {
+                    let (x, y) = (p.x - origin.0, p.y - origin.1);
+                    (x >= 0 && y >= 0)
+                        .then(|| gradient.get_pixel_checked(x as u32, y as u32))
+                        .flatten()
+                })
+                .map(|p| p.0[0] as f32 / 255.0)
+                .sum::<f32>()
+                / points;
+            let (sx, sy) = contour
+                .points
+                .iter()
+                .fold((0.0, 0.0), |(sx, sy), p| (sx + p.x as f32, sy + p.y as f32));
+            let distance = (sx / points - middle.0).hypot(sy / points - middle.1);
+            let closeness = (1.0 - distance / reach.max(1.0)).max(0.0);
+            self.length * contour.points.len() as f32 / longest
+                + self.gradient * strength
+                + self.center * closeness
+        };
+
+        let mut scored: Vec<(f32, (usize, &'a Contour<i32>))> = contours
+            .iter()
+            .map(|&(index, contour)| (score(contour), (index, contour)))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (slot, (_, entry)) in contours.iter_mut().zip(scored) {
+            *slot = entry;
+        }
+    }
+}
+
+/// Sobel gradient magnitude scaled to 0-255.
+pub fn gradient_magnitude(gray: &GrayImage) -> GrayImage {
+    let gx = horizontal_sobel(gray);
+    let gy = vertical_sobel(gray);
+    GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+        let dx = gx.get_pixel(x, y).0[0] as f32;
+        let dy = gy.get_pixel(x, y).0[0] as f32;
+        Luma([(dx.hypot(dy) / SOBEL_MAX * 255.0).min(255.0) as u8])
+    })
+}
+
+/// Greedy visiting order: starting from the first contour, always go to the
+/// unvisited contour whose start is nearest to where the last one ended.
+pub fn reorder_contours_nearest_neighbour<C: Borrow<Contour<i32>>>(contours: &[C]) -> Vec<usize> {
+    let ends: Vec<Option<(Point<i32>, Point<i32>)>> = contours
+        .iter()
+        .map(|contour| {
+            let points = &contour.borrow().points;
+            Some((*points.first()?, *points.last()?))
+        })
+        .collect();
+    let mut visited = vec![false; contours.len()];
+    let mut order = Vec::with_capacity(contours.len());
+    let mut position: Option<Point<i32>> = None;
+
+    while order.len() < contours.len() {
+        let next =
+            match position {
+                None => visited.iter().position(|v| !v),
+                Some(from) => (0..contours.len())
+                    .filter(|&i| !visited[i])
+                    .min_by_key(|&i| match ends[i] {
+                        Some((start, _)) => {
+                            let (dx, dy) = ((start.x - from.x) as i64, (start.y - from.y) as i64);
+                            dx * dx + dy * dy
+                        }
+                        // Empty contours cost nothing to visit.
+                        None => 0,
+                    }),
+            };
+        let Some(next) = next else {
+            break;
+        };
+        visited[next] = true;
+        order.push(next);
+        if let Some((_, end)) = ends[next] {
+            position = Some(end);
+        }
+    }
+    order
+}
@@ -0,0 +1,164 @@
+use eframe::egui;
+use image::RgbImage;
+use imageproc::contours::Contour;
+
+pub const MIN_PALETTE: u8 = 2;
+pub const MAX_PALETTE: u8 = 16;
+/// Pixels beyond this are skipped evenly; k-means doesn't need all of them.
+const MAX_SAMPLES: usize = 10_000;
+const ITERATIONS: usize = 20;
+
+/// The image's dominant colors and the one each contour is drawn in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub colors: Vec<[u8; 3]>,
+    /// Palette indices in the order their strokes are drawn.
+    pub order: Vec<usize>,
+    /// Palette index of each contour, outlines first, then hatches.
+    pub assignment: Vec<usize>,
+}
+
+impl Palette {
+    /// Quantizes `image` to `size` colors and gives each contour the one
+    /// nearest the average color under its points. The contours are in
+    /// image space.
+    pub fn new<'a>(
+        image: &RgbImage,
+        size: u8,
+        contours: impl IntoIterator<Item = &'a Contour<i32>>,
+    ) -> Self {
+        let centroids = kmeans(image, size.clamp(MIN_PALETTE, MAX_PALETTE) as usize);
+        let assignment = contours
+            .into_iter()
+            .map(|contour| {
+                contour_color(image, contour).map_or(0, |color| nearest(&centroids, color))
+            })
+            .collect();
+        Self {
+            colors: centroids
+                .iter()
+                .map(|c| c.map(|v| v.round().clamp(0.0, 255.0) as u8))
+                .collect(),
+            order: (0..centroids.len()).collect(),
+            assignment,
+        }
+    }
+
+    pub fn color_of(&self, index: usize) -> Option<[u8; 3]> {
+        self.assignment.get(index).map(|&i| self.colors[i])
+    }
+
+    /// Where the contour's color comes in the draw order.
+    pub fn rank(&self, index: usize) -> usize {
+        self.assignment
+            .get(index)
+            .and_then(|color| self.order.iter().position(|i| i == color))
+            .unwrap_or(usize::MAX)
+    }
+}
+
+/// `rrggbb`, the form color pickers take in their hex field.
+pub fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("{r:02x}{g:02x}{b:02x}")
+}
+
+fn kmeans(image: &RgbImage, k: usize) -> Vec<[f32; 3]> {
+    let step = (image.pixels().len() / MAX_SAMPLES).max(1);
+    let samples: Vec<[f32; 3]> = image
+        .pixels()
+        .step_by(step)
+        .map(|p| p.0.map(|v| v as f32))
+        .collect();
+    let Some(&first) = samples.first() else {
+        return vec![];
+    };
+
+    // Farthest-point seeding spreads the starting centroids over the colors
+    // that are actually there, and keeps the result the same between runs.
+    let mut centroids = vec![first];
+    while centroids.len() < k {
+        let farthest = samples
+            .iter()
+            .map(|s| (s, distance(&centroids[nearest(&centroids, *s)], s)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, d)| *d > 0.0);
+        let Some((&sample, _)) = farthest else {
+            break;
+        };
+        centroids.push(sample);
+    }
+
+    for _ in 0..ITERATIONS {
+        let mut sums = vec![[0.0f32; 3]; centroids.len()];
+        let mut counts = vec![0usize; centroids.len()];
+        for sample in &samples {
+            let i = nearest(&centroids, *sample);
+            (0..3).for_each(|c| sums[i][c] += sample[c]);
+            counts[i] += 1;
+        }
+        let mut moved = false;
+        for ((centroid, sum), count) in centroids.iter_mut().zip(&sums).zip(&counts) {
+            if *count == 0 {
+                continue;
+            }
+            let mean = sum.map(|v| v / *count as f32);
+            moved |= distance(centroid, &mean) > 0.25;
+            *centroid = mean;
+        }
+        if !moved {
+            break;
+        }
+    }
+    centroids
+}
+
+fn contour_color(image: &RgbImage, contour: &Contour<i32>) -> Option<[f32; 3]> {
+    let (width, height) = image.dimensions();
+    if contour.points.is_empty() || width == 0 || height == 0 {
+        return None;
+    }
+    let mut sum = [0.0f32; 3];
+    for point in &contour.points {
+        let x = point.x.clamp(0, width as i32 - 1) as u32;
+        let y = point.y.clamp(0, height as i32 - 1) as u32;
+        let pixel = image.get_pixel(x, y).0;
+        (0..3).for_each(|c| sum[c] += pixel[c] as f32);
+    }
+    Some(sum.map(|v| v / contour.points.len() as f32))
+}
+
+fn nearest(centroids: &[[f32; 3]], color: [f32; 3]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|a, b| distance(a.1, &color).total_cmp(&distance(b.1, &color)))
+        .map_or(0, |(i, _)| i)
+}
+
+fn distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    (0..3).map(|c| (a[c] - b[c]).powi(2)).sum()
+}
+
+/// One row per color in draw order, with a button to move it up.
+pub fn palette_order_editor(ui: &mut egui::Ui, palette: &mut Palette) {
+    let mut raise = None;
+    for (position, &index) in palette.order.iter().enumerate() {
+        let [r, g, b] = palette.colors[index];
+        let count = palette.assignment.iter().filter(|&&i| i == index).count();
+        ui.horizontal(|ui| {
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(16.0, 16.0), egui::Sense::hover());
+            ui.painter()
+                .rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+            ui.monospace(format!("#{} ({count})", hex([r, g, b])));
+            if ui
+                .add_enabled(position > 0, egui::Button::new("⬆").small())
+                .clicked()
+            {
+                raise = Some(position);
+            }
+        });
+    }
+    if let Some(position) = raise {
+        palette.order.swap(position - 1, position);
+    }
+}
@@ -0,0 +1,75 @@
+use image::{GrayImage, Luma};
+use imageproc::{contours::Contour, drawing::draw_line_segment_mut};
+
+mod circles;
+mod contourf;
+mod halftone;
+mod hatch;
+mod hilbert;
+mod koch;
+mod peano;
+mod penrose;
+mod spiral;
+mod string_art;
+mod truchet;
+mod voronoi;
+
+pub use circles::concentric_circles;
+pub use contourf::contourf;
+pub use halftone::error_diffusion;
+pub use hatch::hatch_fill;
+pub use hilbert::hilbert_path;
+pub use koch::koch_snowflake;
+pub use peano::peano_scan;
+pub use penrose::penrose;
+pub use spiral::spiral_scan;
+pub use string_art::string_art;
+pub use truchet::truchet;
+pub use voronoi::voronoi_stipple;
+
+/// Rasterizes generated strokes so they can be shown in the preview.
+pub fn render(contours: &[Contour<i32>], width: u32, height: u32) -> GrayImage {
+    let mut image = GrayImage::new(width, height);
+    for contour in contours {
+        render_contour(&mut image, contour, Luma([255]));
+    }
+    image
+}
+
+pub fn render_contour(image: &mut GrayImage, contour: &Contour<i32>, color: Luma<u8>) {
+    match contour.points.as_slice() {
+        [point] => {
+            if let Some(pixel) = image.get_pixel_mut_checked(point.x as u32, point.y as u32) {
+                *pixel = color;
+            }
+        }
+        points => {
+            for pair in points.windows(2) {
+                draw_line_segment_mut(
+                    image,
+                    (pair[0].x as f32, pair[0].y as f32),
+                    (pair[1].x as f32, pair[1].y as f32),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+fn stroke(points: Vec<(f32, f32)>) -> Contour<i32> {
+    Contour::new(
+        points
+            .into_iter()
+            .map(|(x, y)| imageproc::point::Point::new(x.round() as i32, y.round() as i32))
+            .collect(),
+        imageproc::contours::BorderType::Outer,
+        None,
+    )
+}
+
+fn brightness(gray: &GrayImage, x: f32, y: f32) -> Option<u8> {
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+    gray.get_pixel_checked(x as u32, y as u32).map(|p| p.0[0])
+}
@@ -0,0 +1,54 @@
+use std::f32::consts::TAU;
+
+use image::GrayImage;
+use imageproc::contours::Contour;
+
+use super::{brightness, stroke};
+
+/// Rings around the image center, `ring_spacing` apart out to `max_radius`,
+/// drawn only along the arcs where the image is darker than `threshold`. Dark
+/// areas fill in with rings and light ones stay open, a circular halftone.
+pub fn concentric_circles(
+    gray: &GrayImage,
+    max_radius: u32,
+    ring_spacing: f32,
+    threshold: u8,
+) -> Vec<Contour<i32>> {
+    let spacing = ring_spacing.max(1.0);
+    let center = (gray.width() as f32 / 2.0, gray.height() as f32 / 2.0);
+    let mut contours = vec![];
+    let mut radius = spacing;
+    while radius <= max_radius as f32 {
+        // About one sample per pixel of circumference.
+        let samples = (TAU * radius).ceil() as usize;
+        let ring: Vec<((f32, f32), bool)> = (0..samples)
+            .map(|i| {
+                let (sin, cos) = (TAU * i as f32 / samples as f32).sin_cos();
+                let (x, y) = (center.0 + radius * cos, center.1 + radius * sin);
+                let dark = brightness(gray, x, y).is_some_and(|value| value < threshold);
+                ((x, y), dark)
+            })
+            .collect();
+        radius += spacing;
+
+        // Start from a light sample so an arc over the seam stays in one piece.
+        let Some(start) = ring.iter().position(|(_, dark)| !dark) else {
+            let mut points: Vec<_> = ring.iter().map(|(point, _)| *point).collect();
+            points.extend(points.first().copied());
+            contours.push(stroke(points));
+            continue;
+        };
+        let mut arc = vec![];
+        for (point, dark) in ring[start..].iter().chain(&ring[..start]) {
+            if *dark {
+                arc.push(*point);
+            } else if !arc.is_empty() {
+                contours.push(stroke(std::mem::take(&mut arc)));
+            }
+        }
+        if !arc.is_empty() {
+            contours.push(stroke(arc));
+        }
+    }
+    contours
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use image::GrayImage;
+use imageproc::contours::Contour;
+
+use super::stroke;
+
+/// Hatch spacing in the darkest band; each lighter band is one step sparser.
+const BAND_SPACING: u32 = 3;
+
+/// A cell edge: horizontal edges run right from (x, y), vertical ones down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Edge {
+    Horizontal(u32, u32),
+    Vertical(u32, u32),
+}
+
+/// Topographic rendering: iso-brightness lines at `levels` evenly spaced
+/// levels (marching squares), with horizontal hatching between each pair of
+/// lines that gets denser in the darker bands. The lightest band stays blank.
+pub fn contourf(gray: &GrayImage, levels: u8) -> Vec<Contour<i32>> {
+    let levels = levels.max(1) as u32;
+    let thresholds: Vec<f32> = (1..=levels)
+        .map(|i| 255.0 * i as f32 / (levels + 1) as f32)
+        .collect();
+
+    let mut contours = vec![];
+    for &level in &thresholds {
+        contours.extend(iso_lines(gray, level));
+    }
+    contours.extend(band_hatches(gray, &thresholds));
+    contours
+}
+
+fn iso_lines(gray: &GrayImage, level: f32) -> Vec<Contour<i32>> {
+    let (width, height) = gray.dimensions();
+    if width < 2 || height < 2 {
+        return vec![];
+    }
+    let value = |x: u32, y: u32| gray.get_pixel(x, y).0[0] as f32;
+    let position = |edge: Edge| -> (f32, f32) {
+        let (a, b, (x, y), horizontal) = match edge {
+            Edge::Horizontal(x, y) => (value(x, y), value(x + 1, y), (x, y), true),
+            Edge::Vertical(x, y) => (value(x, y), value(x, y + 1), (x, y), false),
+        };
+        let t = if (b - a).abs() > f32::EPSILON {
+            ((level - a) / (b - a)).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        if horizontal {
+            (x as f32 + t, y as f32)
+        } else {
+            (x as f32, y as f32 + t)
+        }
+    };
+
+    let mut segments: Vec<(Edge, Edge)> = vec![];
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let corners = [
+                value(x, y),
+                value(x + 1, y),
+                value(x + 1, y + 1),
+                value(x, y + 1),
+            ];
+            let inside = corners.map(|v| v >= level);
+            let top = Edge::Horizontal(x, y);
+            let right = Edge::Vertical(x + 1, y);
+            let bottom = Edge::Horizontal(x, y + 1);
+            let left = Edge::Vertical(x, y);
+            let crossed: Vec<Edge> = [
+                (top, inside[0] != inside[1]),
+                (right, inside[1] != inside[2]),
+                (bottom, inside[2] != inside[3]),
+                (left, inside[3] != inside[0]),
+            ]
+            .into_iter()
+            .filter_map(|(edge, crossed)| crossed.then_some(edge))
+            .collect();
+            match crossed.as_slice() {
+                [a, b] => segments.push((*a, *b)),
+                [_, _, _, _] => {
+                    // Saddle: the cell center decides which corners connect.
+                    let center = corners.iter().sum::<f32>() / 4.0 >= level;
+                    if center == inside[0] {
+                        segments.push((top, right));
+                        segments.push((bottom, left));
+                    } else {
+                        segments.push((left, top));
+                        segments.push((right, bottom));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Every edge is shared by at most two segments, so chaining them by
+    // their edges turns the pieces back into polylines.
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        by_edge.entry(*a).or_default().push(i);
+        by_edge.entry(*b).or_default().push(i);
+    }
+    let mut used = vec![false; segments.len()];
+    let next_segment = |edge: Edge, used: &mut [bool]| -> Option<Edge> {
+        let &i = by_edge.get(&edge)?.iter().find(|&&i| !used[i])?;
+        used[i] = true;
+        let (a, b) = segments[i];
+        Some(if a == edge { b } else { a })
+    };
+
+    let mut lines = vec![];
+    for i in 0..segments.len() {
+        if used[i] {
+            continue;
+        }
+        used[i] = true;
+        let (start, end) = segments[i];
+        let mut forward = vec![start, end];
+        while let Some(edge) = next_segment(*forward.last().unwrap(), &mut used) {
+            forward.push(edge);
+        }
+        let mut backward = vec![];
+        let mut edge = start;
+        while let Some(previous) = next_segment(edge, &mut used) {
+            backward.push(previous);
+            edge = previous;
+        }
+        backward.reverse();
+        backward.extend(forward);
+        lines.push(stroke(backward.into_iter().map(position).collect()));
+    }
+    lines
+}
+
+fn band_hatches(gray: &GrayImage, thresholds: &[f32]) -> Vec<Contour<i32>> {
+    let band = |v: u8| thresholds.partition_point(|&level| level <= v as f32);
+    let mut hatches = vec![];
+    for y in 0..gray.height() {
+        let mut run: Option<(u32, usize)> = None;
+        for x in 0..=gray.width() {
+            let current = (x < gray.width()).then(|| band(gray.get_pixel(x, y).0[0]));
+            if let Some((start, b)) = run {
+                if current == Some(b) {
+                    continue;
+                }
+                let spacing = BAND_SPACING * (b as u32 + 1);
+                if b < thresholds.len() && y % spacing == 0 && x - start > 1 {
+                    hatches.push(stroke(vec![
+                        (start as f32, y as f32),
+                        ((x - 1) as f32, y as f32),
+                    ]));
+                }
+            }
+            run = current.map(|b| (x, b));
+        }
+    }
+    hatches
+}
@@ -0,0 +1,41 @@
+use image::GrayImage;
+use imageproc::{contours::Contour, point::Point};
+
+use super::hilbert::hilbert_index;
+
+const THRESHOLD: f32 = 128.0;
+
+/// Floyd-Steinberg dithering, one click per black pixel. The clicks are
+/// ordered along a Hilbert curve so the pen mostly hops to a neighbour.
+pub fn error_diffusion(gray: &GrayImage) -> Vec<Contour<i32>> {
+    let (width, height) = gray.dimensions();
+    let mut values: Vec<f32> = gray.pixels().map(|p| p.0[0] as f32).collect();
+    let mut dots = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let i = (y * width + x) as usize;
+            let old = values[i];
+            let new = if old < THRESHOLD { 0.0 } else { 255.0 };
+            if new == 0.0 {
+                dots.push(Point::new(x as i32, y as i32));
+            }
+            let error = old - new;
+            let mut spread = |dx: i32, dy: u32, weight: f32| {
+                let (nx, ny) = (x as i32 + dx, y + dy);
+                if nx >= 0 && (nx as u32) < width && ny < height {
+                    values[(ny * width + nx as u32) as usize] += error * weight;
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+
+    let side = width.max(height).max(1).next_power_of_two();
+    dots.sort_unstable_by_key(|p| hilbert_index(side, p.x as u32, p.y as u32));
+    dots.into_iter()
+        .map(|point| Contour::new(vec![point], imageproc::contours::BorderType::Outer, None))
+        .collect()
+}
@@ -0,0 +1,61 @@
+use imageproc::contours::Contour;
+
+use super::stroke;
+
+/// How far apart the ends of a contour may be for it to count as closed.
+const CLOSED_TOLERANCE: i32 = 2;
+
+/// Fills every closed contour with parallel lines `spacing` apart, tilted by
+/// `angle_deg`. Lines are sampled every pixel so they draw like any other
+/// stroke.
+pub fn hatch_fill(contours: &[Contour<i32>], angle_deg: f32, spacing: f32) -> Vec<Contour<i32>> {
+    let spacing = spacing.max(1.0);
+    let (sin, cos) = angle_deg.to_radians().sin_cos();
+    // Rotate into a frame where the hatch lines are horizontal.
+    let to_local = |x: f32, y: f32| (x * cos + y * sin, -x * sin + y * cos);
+    let to_screen = |u: f32, v: f32| (u * cos - v * sin, u * sin + v * cos);
+
+    let mut hatches = vec![];
+    for contour in contours.iter().filter(|contour| is_closed(contour)) {
+        let polygon: Vec<(f32, f32)> = contour
+            .points
+            .iter()
+            .map(|p| to_local(p.x as f32, p.y as f32))
+            .collect();
+        let (min_v, max_v) = polygon.iter().fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+            (lo.min(p.1), hi.max(p.1))
+        });
+
+        let mut v = (min_v / spacing).ceil() * spacing;
+        while v <= max_v {
+            let mut crossings: Vec<f32> = polygon
+                .iter()
+                .zip(polygon.iter().cycle().skip(1))
+                .filter(|(a, b)| (a.1 <= v) != (b.1 <= v))
+                .map(|(a, b)| a.0 + (v - a.1) / (b.1 - a.1) * (b.0 - a.0))
+                .collect();
+            crossings.sort_by(f32::total_cmp);
+            for pair in crossings.chunks_exact(2) {
+                let length = pair[1] - pair[0];
+                let steps = length.floor() as usize;
+                hatches.push(stroke(
+                    (0..=steps)
+                        .map(|step| to_screen(pair[0] + step as f32, v))
+                        .collect(),
+                ));
+            }
+            v += spacing;
+        }
+    }
+    hatches
+}
+
+fn is_closed(contour: &Contour<i32>) -> bool {
+    match (contour.points.first(), contour.points.last()) {
+        (Some(first), Some(last)) if contour.points.len() >= 3 => {
+            (first.x - last.x).abs() <= CLOSED_TOLERANCE
+                && (first.y - last.y).abs() <= CLOSED_TOLERANCE
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,45 @@
+use imageproc::{contours::Contour, point::Point};
+
+/// Chains every contour point into one path ordered along a Hilbert curve.
+/// The curve keeps nearby points close in the order, so the path rarely
+/// jumps far and the pen never has to lift.
+pub fn hilbert_path(contours: &[Contour<i32>], width: u32, height: u32) -> Vec<Contour<i32>> {
+    let side = width.max(height).max(1).next_power_of_two();
+    let mut points: Vec<(u64, Point<i32>)> = contours
+        .iter()
+        .flat_map(|contour| contour.points.iter())
+        .filter(|p| p.x >= 0 && p.y >= 0)
+        .map(|p| (hilbert_index(side, p.x as u32, p.y as u32), *p))
+        .collect();
+    if points.is_empty() {
+        return vec![];
+    }
+    points.sort_unstable_by_key(|(index, _)| *index);
+    points.dedup_by_key(|(index, _)| *index);
+    vec![Contour::new(
+        points.into_iter().map(|(_, point)| point).collect(),
+        imageproc::contours::BorderType::Outer,
+        None,
+    )]
+}
+
+/// Position of `(x, y)` along the Hilbert curve filling a `side`x`side` grid.
+pub(super) fn hilbert_index(side: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut index = 0;
+    let mut s = side / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        index += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        // Rotate the quadrant so the sub-curve lines up.
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    index
+}
@@ -0,0 +1,67 @@
+use imageproc::contours::Contour;
+
+use super::stroke;
+
+/// Koch snowflake centered on a `width`×`height` canvas, as one closed
+/// stroke. `side` is the starting triangle's edge, shrunk so the flake fits,
+/// and `rotation` turns it in degrees. Each iteration swaps the middle third
+/// of every edge for an outward bump, so the stroke has 3·4ⁿ segments.
+pub fn koch_snowflake(
+    width: u32,
+    height: u32,
+    iterations: u8,
+    side: f32,
+    rotation: f32,
+) -> Vec<Contour<i32>> {
+    // The original triangle's corners are the farthest points of the flake.
+    let radius = (side / 3f32.sqrt()).min(width.min(height) as f32 / 2.0);
+    if radius < 1.0 {
+        return vec![];
+    }
+    let center = (width as f32 / 2.0, height as f32 / 2.0);
+    let mut points: Vec<(f32, f32)> = (0..3)
+        .map(|i| {
+            let angle = (rotation - 90.0 + 120.0 * i as f32).to_radians();
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        })
+        .collect();
+    // Which side of an edge is outward depends on the winding.
+    let winding = signed_area(&points).signum();
+
+    for _ in 0..iterations {
+        let mut next = Vec::with_capacity(points.len() * 4);
+        for i in 0..points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            let (dx, dy) = ((b.0 - a.0) / 3.0, (b.1 - a.1) / 3.0);
+            let first = (a.0 + dx, a.1 + dy);
+            let second = (a.0 + 2.0 * dx, a.1 + 2.0 * dy);
+            let height = 3f32.sqrt() / 2.0 * winding;
+            let peak = (
+                (first.0 + second.0) / 2.0 + dy * height,
+                (first.1 + second.1) / 2.0 - dx * height,
+            );
+            next.extend([a, first, peak, second]);
+        }
+        points = next;
+    }
+    points.push(points[0]);
+
+    let mut contour = stroke(points);
+    // Deep iterations on a small flake round several points onto one pixel.
+    contour.points.dedup();
+    vec![contour]
+}
+
+fn signed_area(points: &[(f32, f32)]) -> f32 {
+    (0..points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f32>()
+        / 2.0
+}
@@ -0,0 +1,60 @@
+use image::GrayImage;
+use imageproc::{contours::Contour, point::Point};
+
+/// Walks a Peano curve over the image, taking every `sampling_rate`th
+/// point, and strokes along the curve wherever it runs over an edge pixel
+/// brighter than `threshold`. Consecutive hits join into one micro-stroke.
+pub fn peano_scan(edges: &GrayImage, sampling_rate: u8, threshold: u8) -> Vec<Contour<i32>> {
+    let (width, height) = edges.dimensions();
+    let mut order = 0;
+    let mut side = 1u64;
+    while side < width.max(height) as u64 {
+        side *= 3;
+        order += 1;
+    }
+
+    let mut contours = vec![];
+    let mut stroke: Vec<Point<i32>> = vec![];
+    let mut flush = |stroke: &mut Vec<Point<i32>>| {
+        if !stroke.is_empty() {
+            contours.push(Contour::new(
+                std::mem::take(stroke),
+                imageproc::contours::BorderType::Outer,
+                None,
+            ));
+        }
+    };
+    for index in (0..side * side).step_by(sampling_rate.max(1) as usize) {
+        let (x, y) = peano_point(order, index);
+        let hit = edges
+            .get_pixel_checked(x, y)
+            .is_some_and(|pixel| pixel.0[0] > threshold);
+        if hit {
+            stroke.push(Point::new(x as i32, y as i32));
+        } else {
+            flush(&mut stroke);
+        }
+    }
+    flush(&mut stroke);
+    contours
+}
+
+/// Point `index` along the Peano curve on a 3^`order` square grid, from
+/// Peano's own digit construction: each base-3 digit of x and y is the
+/// matching digit of `index`, mirrored (`2 - t`) when the digits of the
+/// other axis before it sum to an odd number.
+fn peano_point(order: u32, index: u64) -> (u32, u32) {
+    let (mut x, mut y) = (0, 0);
+    let (mut x_parity, mut y_parity) = (0, 0);
+    for level in (0..order).rev() {
+        let pair = index / 9u64.pow(level) % 9;
+        let (a, b) = ((pair / 3) as u32, (pair % 3) as u32);
+        let xd = if y_parity % 2 == 1 { 2 - a } else { a };
+        x_parity += a;
+        let yd = if x_parity % 2 == 1 { 2 - b } else { b };
+        y_parity += b;
+        x = x * 3 + xd;
+        y = y * 3 + yd;
+    }
+    (x, y)
+}
@@ -0,0 +1,128 @@
+use std::{collections::HashSet, f32::consts::PI};
+
+use imageproc::{contours::Contour, point::Point};
+
+use super::stroke;
+
+/// Golden ratio, the scale factor between one generation of tiles and the next.
+const PHI: f32 = 1.618_034;
+
+/// Half of a Penrose rhombus, split across the diagonal between its obtuse
+/// corners: a Robinson triangle with apex `a` and base from `b` to `c`.
+#[derive(Debug, Clone, Copy)]
+struct Triangle {
+    thick: bool,
+    a: (f32, f32),
+    b: (f32, f32),
+    c: (f32, f32),
+}
+
+/// Penrose P3 tiling of thin and thick rhombi, grown from a wheel of ten
+/// triangles around the canvas center by `iterations` rounds of deflation.
+/// The wheel spans `scale` times the canvas half-diagonal, so larger scales
+/// give larger tiles. Rhombus edges come out once each, clipped to the canvas.
+pub fn penrose(width: u32, height: u32, iterations: u8, scale: f32) -> Vec<Contour<i32>> {
+    let center = (width as f32 / 2.0, height as f32 / 2.0);
+    let radius = (width as f32).hypot(height as f32) / 2.0 * scale.max(1.0);
+    let at = |angle: f32| {
+        (
+            center.0 + radius * angle.cos(),
+            center.1 + radius * angle.sin(),
+        )
+    };
+    let mut triangles: Vec<Triangle> = (0..10)
+        .map(|i| {
+            let (mut b, mut c) = (
+                at((2 * i - 1) as f32 * PI / 10.0),
+                at((2 * i + 1) as f32 * PI / 10.0),
+            );
+            // Mirror every other triangle so neighbours share their long edges.
+            if i % 2 == 0 {
+                std::mem::swap(&mut b, &mut c);
+            }
+            Triangle {
+                thick: false,
+                a: center,
+                b,
+                c,
+            }
+        })
+        .collect();
+    for _ in 0..iterations {
+        triangles = triangles.iter().flat_map(deflate).collect();
+    }
+
+    // The base of each triangle is the inside diagonal of its rhombus, so
+    // only the two legs are drawn; legs shared between tiles are skipped.
+    let inside = |p: Point<i32>| p.x >= 0 && p.y >= 0 && p.x < width as i32 && p.y < height as i32;
+    let mut drawn = HashSet::new();
+    let mut keep = |from: Point<i32>, to: Point<i32>| {
+        let (from_key, to_key) = ((from.x, from.y), (to.x, to.y));
+        let key = (from_key.min(to_key), from_key.max(to_key));
+        from != to && inside(from) && inside(to) && drawn.insert(key)
+    };
+    let mut contours = vec![];
+    for triangle in &triangles {
+        let legs = stroke(vec![triangle.c, triangle.a, triangle.b]);
+        let [c, a, b] = [legs.points[0], legs.points[1], legs.points[2]];
+        match (keep(c, a), keep(a, b)) {
+            (true, true) => contours.push(legs),
+            (true, false) => contours.push(stroke(vec![triangle.c, triangle.a])),
+            (false, true) => contours.push(stroke(vec![triangle.a, triangle.b])),
+            (false, false) => {}
+        }
+    }
+    contours
+}
+
+/// Splits a triangle into smaller ones by the golden ratio: thin ones into a
+/// thin and a thick, thick ones into two thick and a thin.
+fn deflate(triangle: &Triangle) -> Vec<Triangle> {
+    let split = |from: (f32, f32), to: (f32, f32)| {
+        (
+            from.0 + (to.0 - from.0) / PHI,
+            from.1 + (to.1 - from.1) / PHI,
+        )
+    };
+    let Triangle { thick, a, b, c } = *triangle;
+    if thick {
+        let q = split(b, a);
+        let r = split(b, c);
+        vec![
+            Triangle {
+                thick: true,
+                a: r,
+                b: c,
+                c: a,
+            },
+            Triangle {
+                thick: true,
+                a: q,
+                b: r,
+                c: b,
+            },
+            Triangle {
+                thick: false,
+                a: r,
+                b: q,
+                c: a,
+            },
+        ]
+    } else {
+        let p = split(a, b);
+        vec![
+            Triangle {
+                thick: false,
+                a: c,
+                b: p,
+                c: b,
+            },
+            Triangle {
+                thick: true,
+                a: p,
+                b: c,
+                c: a,
+            },
+        ]
+    }
+}
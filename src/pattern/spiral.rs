@@ -0,0 +1,39 @@
+use std::f32::consts::TAU;
+
+use image::GrayImage;
+use imageproc::contours::Contour;
+
+use super::{brightness, stroke};
+
+/// Walks an Archimedean spiral from the image center and emits a short radial
+/// stroke wherever the image is darker than `threshold`. Darker samples get
+/// longer strokes, which reads as a spiral halftone.
+pub fn spiral_scan(gray: &GrayImage, turns: u32, spacing: f32, threshold: u8) -> Vec<Contour<i32>> {
+    let spacing = spacing.max(1.0);
+    let center = (gray.width() as f32 / 2.0, gray.height() as f32 / 2.0);
+    let end = turns as f32 * TAU;
+    let step = spacing / 2.0;
+
+    let mut contours = vec![];
+    let mut theta = 0.0;
+    while theta < end {
+        let radius = spacing * theta / TAU;
+        let (sin, cos) = theta.sin_cos();
+        let (x, y) = (center.0 + radius * cos, center.1 + radius * sin);
+        theta += step / radius.max(spacing);
+
+        let Some(value) = brightness(gray, x, y) else {
+            continue;
+        };
+        if value >= threshold {
+            continue;
+        }
+        let darkness = 1.0 - value as f32 / threshold as f32;
+        let half = spacing / 2.0 * darkness;
+        contours.push(stroke(vec![
+            (x - half * cos, y - half * sin),
+            (x + half * cos, y + half * sin),
+        ]));
+    }
+    contours
+}
@@ -0,0 +1,79 @@
+use std::f32::consts::TAU;
+
+use image::GrayImage;
+use imageproc::{contours::Contour, point::Point};
+
+use crate::density::line_pixels;
+
+/// Darkness a string takes off each pixel it crosses.
+const STRING_DARKNESS: f32 = 60.0;
+/// Strings between neighbouring pegs only trace the rim.
+const MIN_PEG_GAP: usize = 5;
+
+/// String art: `peg_count` pegs evenly spaced on the largest circle that fits
+/// the image and up to `string_count` strings stretched between them. Each
+/// string starts where the last one ended and goes to the peg whose line is
+/// darkest on average, then lightens the pixels it covers so later strings
+/// head elsewhere. Stops early once no line has darkness left.
+pub fn string_art(gray: &GrayImage, peg_count: u32, string_count: u32) -> Vec<Contour<i32>> {
+    let (width, height) = gray.dimensions();
+    if width == 0 || height == 0 {
+        return vec![];
+    }
+    let count = peg_count.max(3) as usize;
+    let radius = (width.min(height) as f32 - 1.0) / 2.0;
+    let center = ((width - 1) as f32 / 2.0, (height - 1) as f32 / 2.0);
+    let pegs: Vec<Point<i32>> = (0..count)
+        .map(|i| {
+            let (sin, cos) = (TAU * i as f32 / count as f32).sin_cos();
+            Point::new(
+                (center.0 + radius * cos).round() as i32,
+                (center.1 + radius * sin).round() as i32,
+            )
+        })
+        .collect();
+
+    let mut darkness: Vec<f32> = gray.pixels().map(|p| 255.0 - p.0[0] as f32).collect();
+    let trace = |a: Point<i32>, b: Point<i32>, pixels: &mut Vec<usize>| {
+        pixels.clear();
+        line_pixels(a.x, a.y, b.x, b.y, |x, y| {
+            if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                pixels.push((y as u32 * width + x as u32) as usize);
+            }
+        });
+    };
+
+    let min_gap = MIN_PEG_GAP.min(count / 2);
+    let mut pixels = vec![];
+    let mut strings = vec![];
+    let (mut current, mut previous) = (0usize, None);
+    for _ in 0..string_count {
+        let mut best: Option<(usize, f32)> = None;
+        for next in 0..count {
+            let gap = current.abs_diff(next).min(count - current.abs_diff(next));
+            if gap < min_gap.max(1) || Some(next) == previous {
+                continue;
+            }
+            trace(pegs[current], pegs[next], &mut pixels);
+            let score =
+                pixels.iter().map(|&i| darkness[i]).sum::<f32>() / pixels.len().max(1) as f32;
+            if best.is_none_or(|(_, top)| score > top) {
+                best = Some((next, score));
+            }
+        }
+        let Some((next, _)) = best.filter(|(_, score)| *score > 0.0) else {
+            break;
+        };
+        trace(pegs[current], pegs[next], &mut pixels);
+        for &i in &pixels {
+            darkness[i] = (darkness[i] - STRING_DARKNESS).max(0.0);
+        }
+        strings.push(Contour::new(
+            vec![pegs[current], pegs[next]],
+            imageproc::contours::BorderType::Outer,
+            None,
+        ));
+        (previous, current) = (Some(current), next);
+    }
+    strings
+}
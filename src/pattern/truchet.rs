@@ -0,0 +1,72 @@
+use std::f32::consts::FRAC_PI_2;
+
+use image::GrayImage;
+use imageproc::contours::Contour;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::stroke;
+
+/// Tiles at least this bright stay blank.
+const LIGHT: f32 = 170.0;
+/// Tiles darker than this get a pair of extra arcs either side of the main one.
+const DARK: f32 = 85.0;
+
+/// Truchet tiling: each `tile_size` square holds two quarter circles joining
+/// the midpoints of neighbouring edges, bent around one diagonal or the other
+/// at random. Arcs meet across tile edges, so the tiles weave into long
+/// curves. Light tiles are left empty and dark ones are drawn thicker.
+/// Tiles come row by row, every other row reversed, to keep pen travel short.
+pub fn truchet(gray: &GrayImage, tile_size: u32, seed: u64) -> Vec<Contour<i32>> {
+    let size = tile_size.max(2);
+    let (columns, rows) = (gray.width() / size, gray.height() / size);
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Orientations are drawn up front so they don't depend on brightness.
+    let flipped: Vec<bool> = (0..columns * rows).map(|_| rng.gen()).collect();
+
+    let s = size as f32;
+    let mut contours = vec![];
+    for row in 0..rows {
+        for i in 0..columns {
+            let col = if row % 2 == 0 { i } else { columns - 1 - i };
+            let (x0, y0) = (col * size, row * size);
+            let tone = mean_brightness(gray, x0, y0, size);
+            let radii: &[f32] = if tone >= LIGHT {
+                &[]
+            } else if tone >= DARK {
+                &[s / 2.0]
+            } else {
+                &[s / 3.0, s / 2.0, 2.0 * s / 3.0]
+            };
+            let (x0, y0) = (x0 as f32, y0 as f32);
+            // A corner and where its quarter turn starts, clockwise in
+            // screen space.
+            let corners = if flipped[(row * columns + col) as usize] {
+                [((x0 + s, y0), FRAC_PI_2), ((x0, y0 + s), 3.0 * FRAC_PI_2)]
+            } else {
+                [((x0, y0), 0.0), ((x0 + s, y0 + s), 2.0 * FRAC_PI_2)]
+            };
+            for ((cx, cy), start) in corners {
+                for &radius in radii {
+                    let samples = (radius * FRAC_PI_2).ceil().max(2.0) as usize;
+                    contours.push(stroke(
+                        (0..=samples)
+                            .map(|i| {
+                                let angle = start + FRAC_PI_2 * i as f32 / samples as f32;
+                                (cx + radius * angle.cos(), cy + radius * angle.sin())
+                            })
+                            .collect(),
+                    ));
+                }
+            }
+        }
+    }
+    contours
+}
+
+fn mean_brightness(gray: &GrayImage, x0: u32, y0: u32, size: u32) -> f32 {
+    let sum: u32 = (y0..y0 + size)
+        .flat_map(|y| (x0..x0 + size).map(move |x| (x, y)))
+        .map(|(x, y)| gray.get_pixel(x, y).0[0] as u32)
+        .sum();
+    sum as f32 / (size * size) as f32
+}
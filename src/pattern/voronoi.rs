@@ -0,0 +1,144 @@
+use image::{imageops::FilterType, GrayImage};
+use imageproc::contours::Contour;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
+
+use super::stroke;
+
+/// Side of the working image; relaxation cost grows with its area.
+const WORK_SIZE: u32 = 256;
+const LLOYD_ITERATIONS: usize = 12;
+/// Fixed so the same image and count always give the same dots.
+const SEED: u64 = 0x5717;
+
+/// Weighted Voronoi stippling: seeds `count` dots where the image is dark,
+/// then moves each dot to the darkness-weighted centroid of its Voronoi cell
+/// a few times so the dots spread evenly over each tone. Every dot becomes a
+/// single-point contour.
+pub fn voronoi_stipple(gray: &GrayImage, count: u32) -> Vec<Contour<i32>> {
+    let (width, height) = gray.dimensions();
+    if width == 0 || height == 0 || count == 0 {
+        return vec![];
+    }
+    let scale = (WORK_SIZE as f32 / width.max(height) as f32).min(1.0);
+    let (w, h) = (
+        ((width as f32 * scale) as u32).max(1),
+        ((height as f32 * scale) as u32).max(1),
+    );
+    let small = image::imageops::resize(gray, w, h, FilterType::Triangle);
+    let weights: Vec<f32> = small
+        .pixels()
+        .map(|p| 1.0 - p.0[0] as f32 / 255.0)
+        .collect();
+
+    let Ok(darkness) = WeightedIndex::new(&weights) else {
+        return vec![];
+    };
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut sites: Vec<(f32, f32)> = (0..count)
+        .map(|_| {
+            let index = darkness.sample(&mut rng) as u32;
+            (
+                (index % w) as f32 + rng.gen::<f32>(),
+                (index / w) as f32 + rng.gen::<f32>(),
+            )
+        })
+        .collect();
+
+    for _ in 0..LLOYD_ITERATIONS {
+        let grid = Grid::new(&sites, w, h);
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32); sites.len()];
+        for y in 0..h {
+            for x in 0..w {
+                let weight = weights[(y * w + x) as usize];
+                if weight <= 0.0 {
+                    continue;
+                }
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let nearest = grid.nearest(&sites, px, py);
+                let sum = &mut sums[nearest];
+                sum.0 += px * weight;
+                sum.1 += py * weight;
+                sum.2 += weight;
+            }
+        }
+        for (site, (sx, sy, total)) in sites.iter_mut().zip(sums) {
+            if total > 0.0 {
+                *site = (sx / total, sy / total);
+            }
+        }
+    }
+
+    sites
+        .into_iter()
+        .map(|(x, y)| stroke(vec![(x / scale, y / scale)]))
+        .collect()
+}
+
+/// Buckets sites into square cells so nearest-site lookups only scan a few
+/// neighbouring cells.
+struct Grid {
+    cell: f32,
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    fn new(sites: &[(f32, f32)], width: u32, height: u32) -> Self {
+        let cell = ((width * height) as f32 / sites.len() as f32)
+            .sqrt()
+            .max(1.0);
+        let columns = (width as f32 / cell).ceil() as usize;
+        let rows = (height as f32 / cell).ceil() as usize;
+        let mut buckets = vec![vec![]; columns * rows];
+        for (index, (x, y)) in sites.iter().enumerate() {
+            let column = ((x / cell) as usize).min(columns - 1);
+            let row = ((y / cell) as usize).min(rows - 1);
+            buckets[row * columns + column].push(index);
+        }
+        Self {
+            cell,
+            columns,
+            rows,
+            buckets,
+        }
+    }
+
+    fn nearest(&self, sites: &[(f32, f32)], x: f32, y: f32) -> usize {
+        let column = ((x / self.cell) as usize).min(self.columns - 1) as isize;
+        let row = ((y / self.cell) as usize).min(self.rows - 1) as isize;
+        let mut best = (usize::MAX, f32::MAX);
+        for ring in 0.. {
+            // Anything past this ring is at least `ring * cell` away.
+            if best.0 != usize::MAX && (ring as f32 - 1.0) * self.cell > best.1.sqrt() {
+                break;
+            }
+            if ring > self.columns.max(self.rows) as isize {
+                break;
+            }
+            for dy in -ring..=ring {
+                for dx in -ring..=ring {
+                    if dx.abs() != ring && dy.abs() != ring {
+                        continue;
+                    }
+                    let (c, r) = (column + dx, row + dy);
+                    if c < 0 || r < 0 || c >= self.columns as isize || r >= self.rows as isize {
+                        continue;
+                    }
+                    for &index in &self.buckets[r as usize * self.columns + c as usize] {
+                        let (sx, sy) = sites[index];
+                        let distance = (sx - x).powi(2) + (sy - y).powi(2);
+                        if distance < best.1 {
+                            best = (index, distance);
+                        }
+                    }
+                }
+            }
+        }
+        best.0
+    }
+}
@@ -0,0 +1,127 @@
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    VK_B, VK_CONTROL, VK_ESCAPE, VK_F1, VK_MENU, VK_RETURN, VK_SHIFT, VK_SPACE, VK_TAB,
+};
+
+use crate::input::Backend;
+
+/// Timing and input settings that suit a particular drawing app.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppProfile {
+    pub name: String,
+    pub per_point_delay_us: u64,
+    /// How fast the lifted pen travels to the next stroke; slower gives
+    /// the app more time to register the release.
+    pub pen_travel_speed_px_per_us: f32,
+    /// Virtual keys tapped before each stroke, e.g. to reselect the brush.
+    pub pre_stroke_keys: Vec<u16>,
+    /// Brush size the app is expected to use; dots are spaced one brush
+    /// width apart in stipple modes.
+    pub brush_radius: u8,
+    pub backend: Backend,
+    /// Chord that opens the app's color picker with its hex field focused,
+    /// e.g. `Ctrl+Shift+C`. Empty when the app has none.
+    pub color_picker_shortcut: String,
+}
+
+pub fn builtin_profiles() -> Vec<AppProfile> {
+    vec![
+        AppProfile {
+            name: "MS Paint".into(),
+            per_point_delay_us: 200,
+            pen_travel_speed_px_per_us: 10.0,
+            pre_stroke_keys: vec![],
+            brush_radius: 1,
+            backend: Backend::Mouse,
+            color_picker_shortcut: String::new(),
+        },
+        AppProfile {
+            name: "Krita".into(),
+            per_point_delay_us: 100,
+            pen_travel_speed_px_per_us: 5.0,
+            pre_stroke_keys: vec![VK_B.0],
+            brush_radius: 2,
+            backend: Backend::VirtualTablet,
+            color_picker_shortcut: String::new(),
+        },
+        AppProfile {
+            name: "Photoshop".into(),
+            per_point_delay_us: 150,
+            pen_travel_speed_px_per_us: 5.0,
+            pre_stroke_keys: vec![VK_B.0],
+            brush_radius: 2,
+            backend: Backend::VirtualTablet,
+            color_picker_shortcut: String::new(),
+        },
+        AppProfile {
+            name: "Sketchbook".into(),
+            per_point_delay_us: 150,
+            pen_travel_speed_px_per_us: 5.0,
+            pre_stroke_keys: vec![],
+            brush_radius: 2,
+            backend: Backend::VirtualTablet,
+            color_picker_shortcut: String::new(),
+        },
+        // Browsers coalesce pointer events, so canvases there need it slower.
+        AppProfile {
+            name: "Browser canvas".into(),
+            per_point_delay_us: 1000,
+            pen_travel_speed_px_per_us: 2.0,
+            pre_stroke_keys: vec![],
+            brush_radius: 2,
+            backend: Backend::Mouse,
+            color_picker_shortcut: String::new(),
+        },
+    ]
+}
+
+/// Lowercase window title fragments and the profile each one points to.
+const KNOWN_TITLES: [(&str, &str); 9] = [
+    ("krita", "Krita"),
+    ("photoshop", "Photoshop"),
+    ("sketchbook", "Sketchbook"),
+    // paint.net has no profile of its own; Paint's timings suit it.
+    ("paint.net", "MS Paint"),
+    // Paint's titles end in " - Paint".
+    ("- paint", "MS Paint"),
+    ("- 画图", "MS Paint"),
+    ("chrome", "Browser canvas"),
+    ("firefox", "Browser canvas"),
+    ("microsoft\u{200b} edge", "Browser canvas"),
+];
+
+/// Built-in profile for the app a window title belongs to, if it is a
+/// known one.
+pub fn detect_profile(title: &str) -> Option<AppProfile> {
+    let title = title.to_lowercase();
+    let (_, name) = KNOWN_TITLES
+        .iter()
+        .find(|(pattern, _)| title.contains(pattern))?;
+    builtin_profiles()
+        .into_iter()
+        .find(|profile| profile.name == *name)
+}
+
+/// Parses a chord like `Ctrl+Shift+C` into virtual keys, in the order they
+/// go down.
+pub fn parse_shortcut(text: &str) -> Option<Vec<u16>> {
+    text.split('+')
+        .map(|part| match part.trim().to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Some(VK_CONTROL.0),
+            "shift" => Some(VK_SHIFT.0),
+            "alt" => Some(VK_MENU.0),
+            "enter" => Some(VK_RETURN.0),
+            "tab" => Some(VK_TAB.0),
+            "space" => Some(VK_SPACE.0),
+            "esc" => Some(VK_ESCAPE.0),
+            // Letter and digit keys share their codes with uppercase ASCII.
+            name if name.len() == 1 && name.as_bytes()[0].is_ascii_alphanumeric() => {
+                Some(name.as_bytes()[0].to_ascii_uppercase() as u16)
+            }
+            name => name
+                .strip_prefix('f')
+                .and_then(|n| n.parse::<u16>().ok())
+                .filter(|n| (1..=24).contains(n))
+                .map(|n| VK_F1.0 + n - 1),
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+use std::f32::consts::PI;
+
+use image::{imageops::FilterType, GrayImage};
+
+/// Images are shrunk to fit this before correlating; the shift is scaled
+/// back up afterwards.
+const WORK_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+
+    fn conj(self) -> Self {
+        Self {
+            re: self.re,
+            im: -self.im,
+        }
+    }
+}
+
+/// Translation that moves `moving` onto `fixed`, found by phase
+/// correlation: the peak of the normalized cross-power spectrum's inverse
+/// transform sits at the shift. Shifts past half the image wrap around.
+pub fn phase_correlation(fixed: &GrayImage, moving: &GrayImage) -> (i32, i32) {
+    let (width, height) = (
+        fixed.width().max(moving.width()),
+        fixed.height().max(moving.height()),
+    );
+    if width == 0 || height == 0 {
+        return (0, 0);
+    }
+    let scale = (WORK_SIZE as f32 / width.max(height) as f32).min(1.0);
+    let (w, h) = (
+        ((width as f32 * scale) as u32).max(1),
+        ((height as f32 * scale) as u32).max(1),
+    );
+    let (cols, rows) = (
+        w.next_power_of_two() as usize,
+        h.next_power_of_two() as usize,
+    );
+    let spectrum = |image: &GrayImage| {
+        let small = image::imageops::resize(
+            image,
+            ((image.width() as f32 * scale) as u32).max(1),
+            ((image.height() as f32 * scale) as u32).max(1),
+            FilterType::Triangle,
+        );
+        let mut data = vec![Complex::default(); cols * rows];
+        for (x, y, pixel) in small.enumerate_pixels() {
+            // A Hann window keeps the image borders from dominating.
+            let window = hann(x, small.width()) * hann(y, small.height());
+            data[y as usize * cols + x as usize].re = pixel.0[0] as f32 * window;
+        }
+        fft_2d(&mut data, cols, rows, false);
+        data
+    };
+    let (a, b) = (spectrum(fixed), spectrum(moving));
+    let mut cross: Vec<Complex> = a
+        .iter()
+        .zip(&b)
+        .map(|(a, b)| {
+            let product = a.mul(b.conj());
+            let magnitude = product.re.hypot(product.im).max(f32::EPSILON);
+            Complex {
+                re: product.re / magnitude,
+                im: product.im / magnitude,
+            }
+        })
+        .collect();
+    fft_2d(&mut cross, cols, rows, true);
+
+    let peak = (0..cross.len())
+        .max_by(|&i, &j| cross[i].re.total_cmp(&cross[j].re))
+        .unwrap_or(0);
+    let unwrap = |offset: usize, size: usize| {
+        if offset > size / 2 {
+            offset as i32 - size as i32
+        } else {
+            offset as i32
+        }
+    };
+    let (dx, dy) = (unwrap(peak % cols, cols), unwrap(peak / cols, rows));
+    (
+        (dx as f32 / scale).round() as i32,
+        (dy as f32 / scale).round() as i32,
+    )
+}
+
+fn hann(i: u32, size: u32) -> f32 {
+    if size < 2 {
+        return 1.0;
+    }
+    0.5 - 0.5 * (2.0 * PI * i as f32 / (size - 1) as f32).cos()
+}
+
+/// Rows, then columns. Both sides have to be powers of two.
+fn fft_2d(data: &mut [Complex], cols: usize, rows: usize, inverse: bool) {
+    for row in data.chunks_mut(cols) {
+        fft(row, inverse);
+    }
+    let mut column = vec![Complex::default(); rows];
+    for x in 0..cols {
+        for y in 0..rows {
+            column[y] = data[y * cols + x];
+        }
+        fft(&mut column, inverse);
+        for y in 0..rows {
+            data[y * cols + x] = column[y];
+        }
+    }
+}
+
+/// In-place iterative radix-2 Cooley-Tukey. The inverse is left unscaled,
+/// which doesn't move the peak.
+fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        let step = Complex {
+            re: angle.cos(),
+            im: angle.sin(),
+        };
+        for start in (0..n).step_by(len) {
+            let mut twiddle = Complex { re: 1.0, im: 0.0 };
+            for k in 0..len / 2 {
+                let even = data[start + k];
+                let odd = data[start + k + len / 2].mul(twiddle);
+                data[start + k] = Complex {
+                    re: even.re + odd.re,
+                    im: even.im + odd.im,
+                };
+                data[start + k + len / 2] = Complex {
+                    re: even.re - odd.re,
+                    im: even.im - odd.im,
+                };
+                twiddle = twiddle.mul(step);
+            }
+        }
+        len <<= 1;
+    }
+}
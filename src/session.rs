@@ -0,0 +1,62 @@
+use std::{error::Error, fs, path::Path};
+
+use imageproc::{
+    contours::{BorderType, Contour},
+    point::Point,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::ui::PEN_TRAVEL_SPEED;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContourRecord {
+    pub points: Vec<(i32, i32)>,
+}
+
+/// Contours in screen coordinates along with the timing they are drawn
+/// with, so a drawing can be replayed without the source image.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawingSession {
+    pub contours: Vec<ContourRecord>,
+    pub min_points: usize,
+    pub point_delay_us: u64,
+    pub contour_delay_ms: u64,
+    /// Missing from sessions saved before it existed.
+    #[serde(default = "default_pen_travel_speed")]
+    pub pen_travel_speed_px_per_us: f32,
+}
+
+fn default_pen_travel_speed() -> f32 {
+    PEN_TRAVEL_SPEED
+}
+
+impl From<&Contour<i32>> for ContourRecord {
+    fn from(contour: &Contour<i32>) -> Self {
+        Self {
+            points: contour.points.iter().map(|p| (p.x, p.y)).collect(),
+        }
+    }
+}
+
+impl From<ContourRecord> for Contour<i32> {
+    fn from(record: ContourRecord) -> Self {
+        Contour::new(
+            record
+                .points
+                .into_iter()
+                .map(|(x, y)| Point::new(x, y))
+                .collect(),
+            BorderType::Outer,
+            None,
+        )
+    }
+}
+
+pub fn save_session(session: &DrawingSession, path: &Path) -> Result<(), Box<dyn Error>> {
+    fs::write(path, serde_json::to_string_pretty(session)?)?;
+    Ok(())
+}
+
+pub fn load_session(path: &Path) -> Result<DrawingSession, Box<dyn Error>> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
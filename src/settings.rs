@@ -0,0 +1,58 @@
+use std::{collections::BTreeSet, error::Error, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Persisted between runs in `settings.toml` next to the executable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub calibrated_min_delay_us: Option<u64>,
+    /// Measured length of the 100px test line divided by 100.
+    pub px_per_screen_unit: Option<f64>,
+    /// Outer top-left corner of the main window; `None` lets the OS decide.
+    pub window_pos: Option<(f32, f32)>,
+    /// Inner size of the main window.
+    pub window_size: (f32, f32),
+    pub open_sections: BTreeSet<String>,
+    pub target_window: Option<String>,
+    /// Opt out of Windows' DPI scaling at startup.
+    pub fix_dpi: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            calibrated_min_delay_us: None,
+            px_per_screen_unit: None,
+            window_pos: None,
+            window_size: (800.0, 800.0),
+            open_sections: BTreeSet::new(),
+            target_window: None,
+            fix_dpi: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = settings_path().ok_or("no settings path")?;
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Missing or unreadable settings fall back to the defaults.
+pub fn load_settings() -> Settings {
+    settings_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn settings_path() -> Option<PathBuf> {
+    Some(
+        std::env::current_exe()
+            .ok()?
+            .with_file_name("settings.toml"),
+    )
+}
@@ -0,0 +1,50 @@
+use imageproc::{contours::Contour, point::Point};
+
+/// Douglas-Peucker: drops points that lie within `epsilon` pixels of the
+/// line between the points kept around them.
+pub fn simplify_contour(contour: &Contour<i32>, epsilon: f64) -> Vec<Point<i32>> {
+    let points = &contour.points;
+    if points.len() < 3 || epsilon <= 0.0 {
+        return points.clone();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let (index, distance) = (start + 1..end)
+            .map(|i| {
+                (
+                    i,
+                    segment_distance(&points[i], &points[start], &points[end]),
+                )
+            })
+            .fold(
+                (start, 0.0),
+                |best, next| if next.1 > best.1 { next } else { best },
+            );
+        if distance > epsilon {
+            keep[index] = true;
+            stack.push((start, index));
+            stack.push((index, end));
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(point, _)| *point)
+        .collect()
+}
+
+fn segment_distance(p: &Point<i32>, a: &Point<i32>, b: &Point<i32>) -> f64 {
+    let (dx, dy) = ((b.x - a.x) as f64, (b.y - a.y) as f64);
+    let (px, py) = ((p.x - a.x) as f64, (p.y - a.y) as f64);
+    let length = dx * dx + dy * dy;
+    if length == 0.0 {
+        return (px * px + py * py).sqrt();
+    }
+    let t = ((px * dx + py * dy) / length).clamp(0.0, 1.0);
+    ((px - t * dx).powi(2) + (py - t * dy).powi(2)).sqrt()
+}
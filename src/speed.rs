@@ -0,0 +1,112 @@
+use eframe::egui;
+use egui_plot::{Line, Plot, PlotBounds, PlotPoint, Points};
+
+pub const MAX_MULTIPLIER: f64 = 5.0;
+/// How close in screen pixels a click has to land to grab a control point.
+const GRAB_RADIUS: f32 = 8.0;
+
+/// Piecewise-linear curve from how far along a contour the pen is (0.0-1.0)
+/// to a multiplier on the per-point delay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeedProfile {
+    /// Control points sorted by x, always starting at 0.0 and ending at 1.0.
+    pub points: Vec<[f64; 2]>,
+}
+
+impl Default for SpeedProfile {
+    fn default() -> Self {
+        Self {
+            points: vec![[0.0, 1.0], [1.0, 1.0]],
+        }
+    }
+}
+
+impl SpeedProfile {
+    pub fn multiplier(&self, progress: f64) -> f64 {
+        let progress = progress.clamp(0.0, 1.0);
+        for pair in self.points.windows(2) {
+            let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+            if progress <= x1 {
+                if x1 - x0 <= f64::EPSILON {
+                    return y1;
+                }
+                return y0 + (y1 - y0) * (progress - x0) / (x1 - x0);
+            }
+        }
+        self.points.last().map_or(1.0, |point| point[1])
+    }
+
+    fn nearest(&self, plot_ui: &egui_plot::PlotUi, pos: egui::Pos2) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(i, [x, y])| {
+                let screen = plot_ui.screen_from_plot(PlotPoint::new(*x, *y));
+                (i, screen.distance(pos))
+            })
+            .filter(|(_, distance)| *distance <= GRAB_RADIUS)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+}
+
+/// Drag control points to shape the curve, double-click to add one and
+/// right-click to remove it. The end points only move up and down.
+pub fn speed_profile_editor(ui: &mut egui::Ui, profile: &mut SpeedProfile) {
+    let id = ui.id().with("speed_profile");
+    let mut dragging: Option<usize> = ui.data(|data| data.get_temp(id)).flatten();
+
+    Plot::new(id)
+        .width(240.0)
+        .height(120.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .allow_double_click_reset(false)
+        .show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(PlotBounds::from_min_max([0.0, 0.0], [1.0, MAX_MULTIPLIER]));
+            plot_ui.line(Line::new(profile.points.clone()));
+            plot_ui.points(Points::new(profile.points.clone()).radius(4.0));
+
+            let response = plot_ui.response().clone();
+            let pointer = response.interact_pointer_pos().or(response.hover_pos());
+            if response.drag_started() {
+                dragging = pointer.and_then(|pos| profile.nearest(plot_ui, pos));
+            }
+            if response.drag_stopped() {
+                dragging = None;
+            }
+            if response.secondary_clicked() {
+                if let Some(i) = pointer.and_then(|pos| profile.nearest(plot_ui, pos)) {
+                    if i != 0 && i != profile.points.len() - 1 {
+                        profile.points.remove(i);
+                    }
+                }
+            }
+            let Some(coordinate) = plot_ui.pointer_coordinate() else {
+                return;
+            };
+            let y = coordinate.y.clamp(0.0, MAX_MULTIPLIER);
+            if response.double_clicked() {
+                let x = coordinate.x.clamp(0.0, 1.0);
+                let at = profile.points.partition_point(|point| point[0] < x);
+                if at > 0 && at < profile.points.len() {
+                    profile.points.insert(at, [x, y]);
+                }
+            }
+            if let Some(i) = dragging.filter(|_| response.dragged()) {
+                let last = profile.points.len() - 1;
+                let x = if i == 0 || i == last {
+                    profile.points[i][0]
+                } else {
+                    coordinate
+                        .x
+                        .clamp(profile.points[i - 1][0], profile.points[i + 1][0])
+                };
+                profile.points[i] = [x, y];
+            }
+        });
+
+    ui.data_mut(|data| data.insert_temp(id, dragging));
+}
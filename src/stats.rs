@@ -0,0 +1,163 @@
+use std::time::Duration;
+
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+use imageproc::contours::Contour;
+use rust_i18n::t;
+
+const MM_PER_PIXEL: f64 = 25.4 / 96.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct StrokeStat {
+    pub index: usize,
+    pub points: usize,
+    pub duration: Duration,
+}
+
+impl StrokeStat {
+    pub fn points_per_second(&self) -> f64 {
+        let secs = self.duration.as_secs_f64();
+        if secs > 0.0 {
+            self.points as f64 / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Physical path length of a draw at 96 DPI.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DrawingCost {
+    /// Pen-down distance along the contours.
+    pub stroke_mm: f64,
+    /// Pen-up distance between the end of one contour and the next.
+    pub travel_mm: f64,
+}
+
+pub fn drawing_cost<'a>(contours: impl IntoIterator<Item = &'a Contour<i32>>) -> DrawingCost {
+    let length = |a: &imageproc::point::Point<i32>, b: &imageproc::point::Point<i32>| {
+        (((a.x - b.x) as f64).powi(2) + ((a.y - b.y) as f64).powi(2)).sqrt()
+    };
+    let mut cost = DrawingCost::default();
+    let mut last = None;
+    for contour in contours {
+        let (Some(first), Some(end)) = (contour.points.first(), contour.points.last()) else {
+            continue;
+        };
+        if let Some(last) = last {
+            cost.travel_mm += length(last, first);
+        }
+        cost.stroke_mm += contour
+            .points
+            .windows(2)
+            .map(|pair| length(&pair[0], &pair[1]))
+            .sum::<f64>();
+        last = Some(end);
+    }
+    cost.stroke_mm *= MM_PER_PIXEL;
+    cost.travel_mm *= MM_PER_PIXEL;
+    cost
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatColumn {
+    Index,
+    Points,
+    Duration,
+    Speed,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StatSort {
+    pub column: StatColumn,
+    pub descending: bool,
+}
+
+impl Default for StatSort {
+    fn default() -> Self {
+        Self {
+            column: StatColumn::Duration,
+            descending: true,
+        }
+    }
+}
+
+/// Shows the per-stroke timings; clicking a row selects that contour.
+pub fn stats_table(
+    ui: &mut egui::Ui,
+    stats: &[StrokeStat],
+    sort: &mut StatSort,
+    selected: &mut Option<usize>,
+) {
+    let mut rows = stats.to_vec();
+    rows.sort_by(|a, b| {
+        let ordering = match sort.column {
+            StatColumn::Index => a.index.cmp(&b.index),
+            StatColumn::Points => a.points.cmp(&b.points),
+            StatColumn::Duration => a.duration.cmp(&b.duration),
+            StatColumn::Speed => a.points_per_second().total_cmp(&b.points_per_second()),
+        };
+        if sort.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .sense(egui::Sense::click())
+        .max_scroll_height(200.0)
+        .columns(Column::auto().at_least(80.0), 4)
+        .header(20.0, |mut header| {
+            for (column, label) in [
+                (StatColumn::Index, t!("stat_index")),
+                (StatColumn::Points, t!("stat_points")),
+                (StatColumn::Duration, t!("stat_duration")),
+                (StatColumn::Speed, t!("stat_speed")),
+            ] {
+                header.col(|ui| {
+                    let arrow = match (sort.column == column, sort.descending) {
+                        (true, true) => " ⏷",
+                        (true, false) => " ⏶",
+                        _ => "",
+                    };
+                    if ui.button(format!("{label}{arrow}")).clicked() {
+                        if sort.column == column {
+                            sort.descending = !sort.descending;
+                        } else {
+                            *sort = StatSort {
+                                column,
+                                descending: true,
+                            };
+                        }
+                    }
+                });
+            }
+        })
+        .body(|body| {
+            body.rows(18.0, rows.len(), |mut row| {
+                let stat = rows[row.index()];
+                row.set_selected(*selected == Some(stat.index));
+                row.col(|ui| {
+                    ui.label(stat.index.to_string());
+                });
+                row.col(|ui| {
+                    ui.label(stat.points.to_string());
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.1}", stat.duration.as_secs_f64() * 1000.0));
+                });
+                row.col(|ui| {
+                    ui.label(format!("{:.0}", stat.points_per_second()));
+                });
+                if row.response().clicked() {
+                    *selected = if *selected == Some(stat.index) {
+                        None
+                    } else {
+                        Some(stat.index)
+                    };
+                }
+            });
+        });
+}
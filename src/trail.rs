@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+
+use imageproc::point::Point;
+
+const TRAIL_LENGTH: usize = 50;
+
+/// The last few drawn positions, used to avoid going back over a spot that
+/// was just inked.
+pub struct Trail {
+    radius: i32,
+    stroke: usize,
+    points: VecDeque<(Point<i32>, usize, usize)>,
+}
+
+impl Trail {
+    pub fn new(radius: u8) -> Self {
+        Self {
+            radius: radius as i32,
+            stroke: 0,
+            points: VecDeque::with_capacity(TRAIL_LENGTH),
+        }
+    }
+
+    pub fn next_stroke(&mut self) {
+        self.stroke += 1;
+    }
+
+    /// Whether point `index` of the current stroke lands near a recent
+    /// position. The stretch of the stroke just drawn is always nearby, so
+    /// it doesn't count.
+    pub fn revisits(&self, point: &Point<i32>, index: usize) -> bool {
+        if self.radius == 0 {
+            return false;
+        }
+        self.points.iter().any(|(visited, stroke, at)| {
+            let own = *stroke == self.stroke && index - at <= 2 * self.radius as usize;
+            !own && (visited.x - point.x).pow(2) + (visited.y - point.y).pow(2)
+                <= self.radius.pow(2)
+        })
+    }
+
+    pub fn visit(&mut self, point: &Point<i32>, index: usize) {
+        if self.radius == 0 {
+            return;
+        }
+        if self.points.len() == TRAIL_LENGTH {
+            self.points.pop_front();
+        }
+        self.points.push_back((*point, self.stroke, index));
+    }
+}
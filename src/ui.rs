@@ -1,64 +1,435 @@
 use std::{
+    collections::BTreeSet,
     error::Error,
+    f32::consts::TAU,
+    fmt::Write,
     io::Cursor,
     ops::Deref,
-    sync::{Arc, LazyLock},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Barrier, LazyLock},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use arboard::Clipboard;
+use base64::prelude::*;
 use crossbeam::atomic::AtomicCell;
 use eframe::{
     egui::{self, FontFamily::Proportional, FontId, Image, TextStyle::*},
     App, CreationContext,
 };
 use enigo::{Enigo, Mouse, Settings};
-use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, GenericImageView, GrayImage, Luma,
+};
 use imageproc::{
     contours::{self, Contour},
-    edges,
+    drawing::draw_line_segment_mut,
+    filter::{median_filter, sharpen_gaussian},
+    point::Point,
 };
 use nanoid::nanoid;
 use parking_lot::RwLock;
 use rfd::FileDialog;
 use rust_i18n::t;
-use windows::Win32::UI::{
-    Input::KeyboardAndMouse::{GetAsyncKeyState, VK_F1, VK_F2},
-    WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN},
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::POINT,
+    UI::{
+        Input::KeyboardAndMouse::{
+            GetAsyncKeyState, VIRTUAL_KEY, VK_A, VK_CONTROL, VK_ESCAPE, VK_F3, VK_F4, VK_LBUTTON,
+            VK_RETURN, VK_SPACE, VK_TAB, VK_V, VK_Z,
+        },
+        WindowsAndMessaging::{GetCursorPos, GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN},
+    },
 };
 
-use crate::font::load_fonts;
+use crate::{
+    background::{background_mask, dim_contour, is_background},
+    benchmark::find_min_delay,
+    blur::adaptive_blur,
+    capture::{
+        canvas_from_marks, capture_screen, detect_canvas, find_mark, probe_points, ScreenRect,
+    },
+    circle::fits_within,
+    config::{key_name, Config, Hotkey, Hotkeys},
+    contrast::adjust_contrast,
+    density::{density_map, gini, grid_density, stroke_overlap},
+    depth::{depth_at, depth_map},
+    detail::{detail_at, detail_map},
+    dpi,
+    edges_ext::{detect_edges, postprocess_edges, EdgeMethod},
+    export::export_svg,
+    font::load_fonts,
+    geometry::contour_area,
+    hierarchy::hierarchy_tree,
+    history::{History, Param},
+    homography::Homography,
+    input::{stroke_pressure, travel_path, turn_angle, Backend, Input, Pen, PenUpStyle},
+    layers::{composite, BlendMode, Layer, LayerConfig, MAX_LAYERS},
+    order::{gradient_magnitude, reorder_contours_nearest_neighbour, ContourScore, DrawOrder},
+    palette::{hex, palette_order_editor, Palette, MAX_PALETTE, MIN_PALETTE},
+    pattern::{
+        self, concentric_circles, contourf, error_diffusion, hatch_fill, hilbert_path,
+        koch_snowflake, peano_scan, penrose, spiral_scan, string_art, truchet, voronoi_stipple,
+    },
+    profile::{builtin_profiles, detect_profile, parse_shortcut, AppProfile},
+    register::phase_correlation,
+    session::{load_session, save_session, DrawingSession},
+    settings::load_settings,
+    simplify::simplify_contour,
+    speed::{speed_profile_editor, SpeedProfile},
+    stats::{drawing_cost, stats_table, DrawingCost, StatSort, StrokeStat},
+    trail::Trail,
+    wave::WaveDistort,
+    window::window_titles,
+};
 
 pub static STATE: AtomicCell<State> = AtomicCell::new(State::Stop);
 pub static DRAWING: AtomicCell<bool> = AtomicCell::new(false);
+/// Set by F4 during a draw to drop the contour being drawn.
+pub static SKIP: AtomicCell<bool> = AtomicCell::new(false);
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "avif", "jpg", "jpeg", "jfif", "png", "apng", "gif", "webp", "tif", "tiff", "tga", "dds",
+    "bmp", "ico", "hdr", "exr", "pdm", "pam", "ppm", "pgm", "ff", "qoi", "pcx",
+];
+pub const MASK_THRESHOLD: u8 = 128;
+pub const CANNY_STEP: u32 = 5;
+pub const PEN_TRAVEL_SPEED: f32 = 5.0;
+pub const TOAST_DURATION: Duration = Duration::from_millis(1500);
+pub const THUMBNAIL_SPACING: f32 = 5.0;
+pub const MAX_ZOOM: f32 = 10.0;
+pub const PREVIEW_MIN_ZOOM: f32 = 0.1;
+pub const PREVIEW_MAX_ZOOM: f32 = 5.0;
+pub const TEST_LINE_PX: i32 = 100;
+pub const CROP_VARIANCE: f32 = 25.0;
+pub const INJECTION_TOLERANCE: f32 = 5.0;
+/// Time the color picker gets to open, and to close again after the paste.
+pub const PICKER_DELAY: Duration = Duration::from_millis(300);
+pub const PAUSE_POLL: Duration = Duration::from_millis(50);
+/// Half the side of the region captured around each canvas probe.
+pub const PROBE_RADIUS: i32 = 32;
+/// Time the app gets to show a test mark, and to undo it.
+pub const PROBE_DELAY: Duration = Duration::from_millis(150);
+/// Contours up to this many points count as isolated dots for long presses.
+pub const LONG_PRESS_MAX_POINTS: usize = 3;
+/// Longest side of each image in the comparison window.
+pub const COMPARE_SIZE: f32 = 320.0;
+pub const OVERLAY_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 64, 64);
+pub const CANVAS_COLOR: egui::Color32 = egui::Color32::from_rgb(64, 160, 255);
+pub const DRAWN_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(0, 160, 0, 160);
+pub const HIGHLIGHT_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 200, 0);
+pub const STROKE_KEYS: [(VIRTUAL_KEY, &str); 4] = [
+    (VK_RETURN, "Enter"),
+    (VK_SPACE, "Space"),
+    (VK_TAB, "Tab"),
+    (VK_ESCAPE, "Esc"),
+];
 pub static SCREEN: LazyLock<(i32, i32)> =
     LazyLock::new(|| unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) });
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum State {
     Drawing,
+    Paused,
     Stop,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Language {
     Chinese,
     English,
 }
 
+/// [`FilterType`] with the comparisons and serde a UI setting needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl ResizeFilter {
+    pub const ALL: [ResizeFilter; 5] = [
+        ResizeFilter::Nearest,
+        ResizeFilter::Triangle,
+        ResizeFilter::CatmullRom,
+        ResizeFilter::Gaussian,
+        ResizeFilter::Lanczos3,
+    ];
+}
+
+/// How the image is made to fit the box it is resized into, `area` percent
+/// of the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResizeMode {
+    /// Keeps the aspect ratio and leaves blank space on the short side.
+    Fit,
+    /// Keeps the aspect ratio and crops the long side.
+    Fill,
+    /// Squashes the image to the box.
+    Stretch,
+}
+
+impl ResizeMode {
+    pub const ALL: [ResizeMode; 3] = [ResizeMode::Fit, ResizeMode::Fill, ResizeMode::Stretch];
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DrawMode {
+    Line,
+    Stipple { min_spacing_px: u32 },
+    Stipple3D { min_spacing_px: u32 },
+    Spiral,
+    HilbertScan,
+    Voronoi,
+    Contourf,
+    Peano,
+    ConcentricCircles,
+    ErrorDiffusionHalftone,
+    StringArt,
+    Truchet,
+    KochSnowflake,
+    Penrose,
+}
+
+impl DrawMode {
+    /// Generated modes produce their own strokes, so the edge-length filter
+    /// doesn't apply to them.
+    pub fn is_generated(&self) -> bool {
+        matches!(
+            self,
+            DrawMode::Spiral
+                | DrawMode::HilbertScan
+                | DrawMode::Voronoi
+                | DrawMode::Contourf
+                | DrawMode::Peano
+                | DrawMode::ConcentricCircles
+                | DrawMode::ErrorDiffusionHalftone
+                | DrawMode::StringArt
+                | DrawMode::Truchet
+                | DrawMode::KochSnowflake
+                | DrawMode::Penrose
+        )
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Panel {
     pub center: Arc<RwLock<(i32, i32)>>,
     pub area: u32,
+    /// Nearest is fastest for previews, Lanczos3 sharpest for the final draw.
+    pub filter_type: ResizeFilter,
+    pub resize_mode: ResizeMode,
+    pub offset_x: i32,
+    pub offset_y: i32,
+    /// Part of the source image to use, as x, y, width and height.
+    pub pre_crop: Option<[u32; 4]>,
+    pub centering: Arc<AtomicCell<bool>>,
+    pub picked_center: Arc<AtomicCell<Option<Point<i32>>>>,
+    /// A session loaded off the UI thread, waiting to replace the contours.
+    pub imported_session: Arc<RwLock<Option<DrawingSession>>>,
+    pub canvas: Option<ScreenRect>,
+    pub homography: Option<Homography>,
+    pub wave: Option<WaveDistort>,
+    pub calibrating: bool,
+    pub warp_source: Vec<Point<i32>>,
+    pub warp_dest: Vec<Point<i32>>,
+    pub detected_canvas: Arc<RwLock<Option<ScreenRect>>>,
     pub canny_value: u32,
+    pub canny_high_value: u32,
+    pub edge_method: EdgeMethod,
+    pub sweep_start: u32,
+    pub sweep_end: u32,
+    pub sweep_step: u32,
+    pub sweeping: Arc<AtomicCell<bool>>,
     pub canny_image: Arc<RwLock<Option<Img>>>,
     pub resized_img: Arc<RwLock<Option<DynamicImage>>>,
     pub raw_img: Arc<RwLock<Option<DynamicImage>>>,
+    pub layers: Arc<RwLock<Vec<Layer>>>,
     pub lines: Arc<RwLock<Option<Vec<Contour<i32>>>>>,
-    pub point_count: usize,
+    pub hatches: Arc<RwLock<Vec<Contour<i32>>>>,
+    pub simplified: Arc<RwLock<Vec<Contour<i32>>>>,
+    pub density: Arc<RwLock<Option<(Img, f32, u32)>>>,
+    pub measures: Arc<RwLock<Measures>>,
+    /// The filters `measures` was last worked out under.
+    pub measured_with: Option<MeasureKey>,
+    pub comparison: Arc<RwLock<Option<Comparison>>>,
+    /// Opacity of the reference over the screenshot.
+    pub compare_alpha: f32,
+    pub detail: Arc<RwLock<Option<GrayImage>>>,
+    pub depth: Arc<RwLock<Option<GrayImage>>>,
+    pub gradient: Arc<RwLock<Option<GrayImage>>>,
+    pub background: Arc<RwLock<Vec<bool>>>,
+    pub stroke_stats: Arc<RwLock<Vec<StrokeStat>>>,
+    /// Set after a multi-pass draw, whose stroke indices are into each
+    /// pass's contours rather than `lines`.
+    pub stats_from_passes: Arc<AtomicCell<bool>>,
+    pub image_path: Arc<RwLock<Option<PathBuf>>>,
+    pub last_draw_duration: Arc<AtomicCell<Option<Duration>>>,
+    pub draw_started: Arc<AtomicCell<Option<Instant>>>,
+    /// Points the running draw will visit, and how many it has so far.
+    pub total_points: Arc<AtomicCell<usize>>,
+    pub drawn_points: Arc<AtomicCell<usize>>,
+    pub input_errors: Arc<AtomicCell<usize>>,
+    pub invalid_point_count: Arc<AtomicCell<usize>>,
+    pub stats_sort: StatSort,
+    pub highlight: Option<usize>,
+    /// Indices into `lines` left out of the draw; cleared on reload.
+    pub excluded: Arc<RwLock<BTreeSet<usize>>>,
+    pub show_hierarchy: bool,
+    /// Contours with fewer or more points than this are left out.
+    pub min_points: usize,
+    pub max_points: usize,
+    /// Contours enclosing less than this many square pixels are left out.
+    pub min_area: f64,
+    pub min_bounding_radius_px: f32,
+    pub point_delay_us: u64,
+    pub contour_delay_ms: u64,
+    /// How fast the lifted pen travels between strokes; the travel distance
+    /// over this is added to the contour pause.
+    pub pen_travel_speed_px_per_us: f32,
+    pub pre_stroke_keys: Vec<u16>,
+    pub profile: Option<String>,
+    /// Title of the window being drawn into; picking one applies the
+    /// matching built-in profile.
+    pub target_window: Option<String>,
+    pub speed_factor: f32,
+    pub corner_dwell_factor: f32,
+    pub speed_profile: SpeedProfile,
+    pub trail_skip_radius_px: u8,
+    pub grid_snap_first_point: bool,
+    /// Screen position of a grid vertex.
+    pub grid_origin: (i32, i32),
+    pub grid_cell: (u32, u32),
+    pub corner_threshold_deg: f32,
+    pub parallel_workers: u8,
+    pub draw_mode: DrawMode,
+    pub backend: Backend,
+    pub pen_up_style: PenUpStyle,
+    pub pressure_noise_amplitude: f32,
+    pub spiral_turns: u32,
+    pub spiral_spacing: f32,
+    pub darkness_threshold: u8,
+    pub max_radius_px: u32,
+    pub ring_spacing_px: f32,
+    pub brightness_threshold: u8,
+    pub peg_count: u32,
+    pub string_count: u32,
+    pub tile_size_px: u32,
+    /// Seed for the Truchet tile orientations.
+    pub seed: u64,
+    pub koch_iterations: u8,
+    /// Edge of the snowflake's starting triangle.
+    pub koch_size_px: f32,
+    pub koch_rotation: f32,
+    pub penrose_iterations: u8,
+    /// Penrose tile size, as the starting wheel's radius over the canvas
+    /// half-diagonal.
+    pub penrose_scale: f32,
+    pub voronoi_points: u32,
+    pub contourf_levels: u8,
+    /// Peano mode samples every this many curve points.
+    pub sampling_rate: u8,
+    pub depth_scale: f32,
+    pub max_dwell_ms: u64,
+    /// Hold the button this long on isolated dots instead of tracing them,
+    /// for apps that fill on a long press.
+    pub long_press_threshold_ms: Option<u64>,
+    pub hatch: bool,
+    pub hatch_angle: f32,
+    pub hatch_spacing: f32,
+    /// Line drawings are traced once per pass when this isn't empty.
+    pub passes: Vec<LayerConfig>,
+    /// Switch the brush to each contour's color from a k-means palette.
+    pub palette_mapping: bool,
+    pub palette_size: u8,
+    pub palette: Arc<RwLock<Option<Palette>>>,
+    pub color_picker_shortcut: String,
+    pub new_stroke_key: Option<VIRTUAL_KEY>,
+    pub calibrated_min_delay_us: Arc<AtomicCell<Option<u64>>>,
+    /// How long a screen pixel of injected movement turns out on screen.
+    pub px_per_screen_unit: Arc<AtomicCell<f64>>,
+    pub measured_test_line_px: f64,
     pub language: Language,
     pub is_binary: bool,
+    pub invert_colors: bool,
+    pub brightness: i32,
+    /// -1.0 flattens the image to gray, 1.0 leaves it as it is and 3.0
+    /// triples the spread, see [`crate::contrast::contrast_gain`].
+    pub contrast: f32,
+    pub median_radius: u32,
+    pub adaptive_blur_sigma: f32,
+    /// Gaussian blur on the edge detector's input only; 0.0 turns it off.
+    pub blur_sigma: f32,
+    /// Morphology passes over the edge map before contours are traced.
+    pub dilate_iters: u32,
+    pub erode_iters: u32,
+    pub unsharp_mask_sigma: f32,
+    pub unsharp_amount: f32,
+    pub is_mask: Arc<AtomicCell<bool>>,
+    pub skip_background: bool,
+    pub thumbnail_first: bool,
+    pub chunks: u8,
+    pub chunk_pause_seconds: u32,
+    /// The chunk about to start and the seconds until it does.
+    pub chunk_countdown: Arc<AtomicCell<Option<(usize, u32)>>>,
+    pub hide_during_draw: bool,
+    pub always_on_top: bool,
+    pub draw_order: DrawOrder,
+    /// Visit contours nearest-first to cut pen-up travel; overrides `draw_order`.
+    pub optimize_order: bool,
+    pub contour_score: ContourScore,
+    pub max_contours: Option<usize>,
+    pub start_from: Arc<AtomicCell<usize>>,
+    pub show_progress_border: bool,
+    pub border_width: u8,
+    pub awaiting_confirmation: Arc<AtomicCell<bool>>,
+    pub show_overlay: bool,
+    pub show_density: bool,
+    pub show_coverage: bool,
+    pub zoom_scale: f32,
+    pub pan_offset: egui::Vec2,
+    /// Scale of the preview inside its scroll area, set with the wheel.
+    pub preview_zoom: f32,
+    /// Scroll position of the preview, moved with a middle-drag.
+    pub preview_offset: egui::Vec2,
+    /// Program to open exported SVGs with, e.g. `inkscape` on PATH.
+    pub auto_open_svg_in: Option<String>,
+    pub coverage_grid: u32,
+    pub show_simplified: bool,
+    pub simplification_epsilon: f64,
+    pub toast: Option<(String, Instant)>,
+    pub history: History,
+    pub held_keys: Vec<u16>,
+    pub hotkeys: Hotkeys,
+    /// The hotkey waiting for a key press to bind to.
+    pub rebinding: Option<Hotkey>,
+    /// Keys of the collapsible sections left open.
+    pub open_sections: BTreeSet<String>,
+    pub window_pos: Option<egui::Pos2>,
+    /// Set at startup when Windows scales the coordinates we see.
+    pub dpi_scaled: bool,
+    pub fix_dpi: bool,
+    pub window_size: Option<egui::Vec2>,
+    /// The config as last written to disk.
+    pub saved_config: Option<Config>,
 }
 
 #[derive(Debug, Clone)]
@@ -67,19 +438,218 @@ pub struct Img {
     buf: Vec<u8>,
 }
 
+impl Img {
+    fn encode(image: DynamicImage) -> Self {
+        let mut data = Cursor::new(vec![]);
+        image.write_to(&mut data, image::ImageFormat::Png).ok();
+        Self {
+            id: nanoid!(),
+            buf: data.into_inner(),
+        }
+    }
+}
+
+/// The filtered gray image and the edges found in it.
+struct Trace {
+    gray: GrayImage,
+    edges: GrayImage,
+}
+
+/// The resized image and the screenshot of the drawing lined up with it.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    reference: Img,
+    drawn: Img,
+    size: [u32; 2],
+}
+
+/// What the drawable contours come to, worked out once per reload or
+/// filter change rather than every frame.
+#[derive(Debug, Clone, Default)]
+pub struct Measures {
+    pub drawable: usize,
+    pub cost: DrawingCost,
+    pub overlap: Option<f32>,
+    pub coverage: Option<Vec<f32>>,
+}
+
+/// The settings `Measures` depend on besides the contours themselves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureKey {
+    draw_mode: DrawMode,
+    skip_background: bool,
+    excluded: usize,
+    min_points: usize,
+    max_points: usize,
+    min_bounding_radius_px: f32,
+    min_area: f64,
+    simplification_epsilon: f64,
+    show_coverage: bool,
+    coverage_grid: u32,
+}
+
 impl Default for Panel {
     fn default() -> Self {
         Self {
             center: Arc::new(RwLock::new((0, 0))),
             area: 70,
+            filter_type: ResizeFilter::Lanczos3,
+            resize_mode: ResizeMode::Fit,
+            offset_x: 0,
+            offset_y: 0,
+            pre_crop: None,
+            centering: Arc::new(AtomicCell::new(false)),
+            picked_center: Arc::new(AtomicCell::new(None)),
+            imported_session: Arc::new(RwLock::new(None)),
+            canvas: None,
+            homography: None,
+            wave: None,
+            calibrating: false,
+            warp_source: vec![],
+            warp_dest: vec![],
+            detected_canvas: Arc::new(RwLock::new(None)),
             canny_value: 25,
+            canny_high_value: 75,
+            edge_method: EdgeMethod::Canny,
+            sweep_start: 10,
+            sweep_end: 100,
+            sweep_step: 10,
+            sweeping: Arc::new(AtomicCell::new(false)),
             canny_image: Arc::new(RwLock::new(None)),
             resized_img: Arc::new(RwLock::new(None)),
             raw_img: Arc::new(RwLock::new(None)),
+            layers: Arc::new(RwLock::new(vec![])),
             lines: Arc::new(RwLock::new(None)),
-            point_count: 10,
+            hatches: Arc::new(RwLock::new(vec![])),
+            simplified: Arc::new(RwLock::new(vec![])),
+            density: Arc::new(RwLock::new(None)),
+            measures: Arc::new(RwLock::new(Measures::default())),
+            measured_with: None,
+            comparison: Arc::new(RwLock::new(None)),
+            compare_alpha: 0.5,
+            detail: Arc::new(RwLock::new(None)),
+            depth: Arc::new(RwLock::new(None)),
+            gradient: Arc::new(RwLock::new(None)),
+            background: Arc::new(RwLock::new(vec![])),
+            stroke_stats: Arc::new(RwLock::new(vec![])),
+            stats_from_passes: Arc::new(AtomicCell::new(false)),
+            image_path: Arc::new(RwLock::new(None)),
+            last_draw_duration: Arc::new(AtomicCell::new(None)),
+            draw_started: Arc::new(AtomicCell::new(None)),
+            total_points: Arc::new(AtomicCell::new(0)),
+            drawn_points: Arc::new(AtomicCell::new(0)),
+            input_errors: Arc::new(AtomicCell::new(0)),
+            invalid_point_count: Arc::new(AtomicCell::new(0)),
+            stats_sort: StatSort::default(),
+            highlight: None,
+            excluded: Default::default(),
+            show_hierarchy: false,
+            min_points: 10,
+            max_points: usize::MAX,
+            min_area: 0.0,
+            min_bounding_radius_px: 0.0,
+            point_delay_us: 100,
+            contour_delay_ms: 100,
+            pen_travel_speed_px_per_us: PEN_TRAVEL_SPEED,
+            pre_stroke_keys: vec![],
+            profile: None,
+            target_window: None,
+            speed_factor: 0.0,
+            corner_dwell_factor: 1.0,
+            speed_profile: SpeedProfile::default(),
+            trail_skip_radius_px: 0,
+            grid_snap_first_point: false,
+            grid_origin: (0, 0),
+            grid_cell: (10, 10),
+            corner_threshold_deg: 45.0,
+            parallel_workers: 1,
+            draw_mode: DrawMode::Line,
+            backend: Backend::Mouse,
+            pen_up_style: PenUpStyle::Direct,
+            pressure_noise_amplitude: 0.0,
+            spiral_turns: 100,
+            spiral_spacing: 5.0,
+            darkness_threshold: 128,
+            max_radius_px: 400,
+            ring_spacing_px: 4.0,
+            brightness_threshold: 128,
+            peg_count: 200,
+            string_count: 1500,
+            tile_size_px: 20,
+            koch_iterations: 4,
+            koch_size_px: 500.0,
+            koch_rotation: 0.0,
+            penrose_iterations: 5,
+            penrose_scale: 1.0,
+            seed: 0,
+            voronoi_points: 1000,
+            contourf_levels: 8,
+            sampling_rate: 2,
+            depth_scale: 4.0,
+            max_dwell_ms: 200,
+            long_press_threshold_ms: None,
+            hatch: false,
+            hatch_angle: 45.0,
+            hatch_spacing: 8.0,
+            passes: vec![],
+            palette_mapping: false,
+            palette_size: 6,
+            palette: Arc::new(RwLock::new(None)),
+            color_picker_shortcut: String::new(),
+            new_stroke_key: None,
+            calibrated_min_delay_us: Arc::new(AtomicCell::new(None)),
+            px_per_screen_unit: Arc::new(AtomicCell::new(1.0)),
+            measured_test_line_px: TEST_LINE_PX as f64,
             language: Language::Chinese,
             is_binary: false,
+            invert_colors: false,
+            brightness: 0,
+            contrast: 1.0,
+            median_radius: 0,
+            adaptive_blur_sigma: 0.0,
+            blur_sigma: 0.0,
+            dilate_iters: 0,
+            erode_iters: 0,
+            unsharp_mask_sigma: 0.0,
+            unsharp_amount: 1.0,
+            is_mask: Arc::new(AtomicCell::new(false)),
+            skip_background: false,
+            thumbnail_first: false,
+            chunks: 1,
+            chunk_pause_seconds: 30,
+            chunk_countdown: Arc::new(AtomicCell::new(None)),
+            hide_during_draw: false,
+            always_on_top: true,
+            draw_order: DrawOrder::Original,
+            optimize_order: false,
+            contour_score: ContourScore::default(),
+            max_contours: None,
+            start_from: Arc::new(AtomicCell::new(0)),
+            show_progress_border: false,
+            border_width: 1,
+            awaiting_confirmation: Arc::new(AtomicCell::new(false)),
+            show_overlay: false,
+            show_density: false,
+            show_coverage: false,
+            zoom_scale: 1.0,
+            pan_offset: egui::Vec2::ZERO,
+            preview_zoom: 1.0,
+            preview_offset: egui::Vec2::ZERO,
+            auto_open_svg_in: None,
+            coverage_grid: 16,
+            show_simplified: false,
+            simplification_epsilon: 1.0,
+            toast: None,
+            history: History::default(),
+            held_keys: vec![],
+            hotkeys: Hotkeys::default(),
+            rebinding: None,
+            open_sections: BTreeSet::new(),
+            window_pos: None,
+            dpi_scaled: false,
+            fix_dpi: false,
+            window_size: None,
+            saved_config: None,
         }
     }
 }
@@ -99,107 +669,729 @@ impl Panel {
         ]
         .into();
         cc.egui_ctx.set_style(style);
-        Box::new(Panel::default())
-    }
-
-    fn open_image(&self) {
-        let image_center = self.center.clone();
-        let area = self.area;
-        let canny_value = self.canny_value;
-        let canny_image = self.canny_image.clone();
-        let lines = self.lines.clone();
-        let resized_img = self.resized_img.clone();
-        let raw_img = self.raw_img.clone();
-        let is_binary = self.is_binary;
+        let mut panel = Panel::default();
+        let config = Config::load();
+        panel.apply_config(config.clone());
+        panel.saved_config = Some(config);
+        // After the config, so a profile detected for the target window wins.
+        panel.apply_settings();
+        panel.verify_screen_resolution();
+        cc.egui_ctx
+            .send_viewport_cmd(egui::ViewportCommand::WindowLevel(window_level(
+                panel.always_on_top,
+            )));
+        Box::new(panel)
+    }
+
+    fn apply_settings(&mut self) {
+        let settings = load_settings();
+        self.open_sections = settings.open_sections;
+        self.calibrated_min_delay_us
+            .store(settings.calibrated_min_delay_us);
+        self.px_per_screen_unit
+            .store(settings.px_per_screen_unit.unwrap_or(1.0));
+        self.target_window = settings.target_window;
+        self.detect_profile();
+        self.fix_dpi = settings.fix_dpi;
+    }
+
+    /// Looks for DPI scaling, which makes the screen size and cursor
+    /// positions we see differ from the real pixels.
+    fn verify_screen_resolution(&mut self) {
+        self.dpi_scaled = dpi::is_scaled();
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            language: self.language,
+            edge_method: self.edge_method,
+            filter_type: self.filter_type,
+            resize_mode: self.resize_mode,
+            canny_value: self.canny_value,
+            canny_high_value: self.canny_high_value,
+            area: self.area,
+            min_points: self.min_points,
+            max_points: (self.max_points != usize::MAX).then_some(self.max_points),
+            min_area: self.min_area,
+            point_delay_us: self.point_delay_us,
+            contour_delay_ms: self.contour_delay_ms,
+            pen_travel_speed_px_per_us: self.pen_travel_speed_px_per_us,
+            backend: self.backend,
+            darkness_threshold: self.darkness_threshold,
+            brightness_threshold: self.brightness_threshold,
+            median_radius: self.median_radius,
+            adaptive_blur_sigma: self.adaptive_blur_sigma,
+            blur_sigma: self.blur_sigma,
+            unsharp_mask_sigma: self.unsharp_mask_sigma,
+            unsharp_amount: self.unsharp_amount,
+            dilate_iters: self.dilate_iters,
+            erode_iters: self.erode_iters,
+            always_on_top: self.always_on_top,
+            brightness: self.brightness,
+            contrast: self.contrast,
+            invert_colors: self.invert_colors,
+            hotkeys: self.hotkeys,
+            draw_mode: self.draw_mode,
+            pen_up_style: self.pen_up_style,
+            draw_order: self.draw_order,
+        }
+    }
+
+    fn apply_config(&mut self, config: Config) {
+        self.language = config.language;
+        set_locale(self.language);
+        self.edge_method = config.edge_method;
+        self.filter_type = config.filter_type;
+        self.resize_mode = config.resize_mode;
+        self.canny_value = config.canny_value;
+        self.canny_high_value = config.canny_high_value;
+        self.area = config.area;
+        self.min_points = config.min_points;
+        self.max_points = config.max_points.unwrap_or(usize::MAX);
+        self.min_area = config.min_area;
+        self.point_delay_us = config.point_delay_us;
+        self.contour_delay_ms = config.contour_delay_ms;
+        self.pen_travel_speed_px_per_us = config.pen_travel_speed_px_per_us;
+        self.backend = config.backend;
+        self.darkness_threshold = config.darkness_threshold;
+        self.brightness_threshold = config.brightness_threshold;
+        self.median_radius = config.median_radius;
+        self.adaptive_blur_sigma = config.adaptive_blur_sigma;
+        self.blur_sigma = config.blur_sigma;
+        self.unsharp_mask_sigma = config.unsharp_mask_sigma;
+        self.unsharp_amount = config.unsharp_amount;
+        self.dilate_iters = config.dilate_iters;
+        self.erode_iters = config.erode_iters;
+        self.always_on_top = config.always_on_top;
+        self.brightness = config.brightness;
+        self.contrast = config.contrast;
+        self.invert_colors = config.invert_colors;
+        self.hotkeys = config.hotkeys;
+        self.draw_mode = config.draw_mode;
+        self.pen_up_style = config.pen_up_style;
+        self.draw_order = config.draw_order;
+        self.check_backend();
+    }
+
+    /// Writes the config once the pointer is up, so a slider drag saves
+    /// once on release rather than on every step.
+    fn autosave_config(&mut self, ctx: &egui::Context) {
+        if ctx.input(|input| input.pointer.any_down()) {
+            return;
+        }
+        let config = self.config();
+        if self.saved_config.as_ref() != Some(&config) && config.save().is_ok() {
+            self.saved_config = Some(config);
+        }
+    }
+
+    /// Collapsible section that opens the way it was left on the last run.
+    fn section(&self, key: &str, title: impl Into<egui::WidgetText>) -> egui::CollapsingHeader {
+        egui::CollapsingHeader::new(title)
+            .id_salt(key)
+            .default_open(self.open_sections.contains(key))
+    }
+
+    fn remember_section<R>(&mut self, key: &str, response: &egui::CollapsingResponse<R>) {
+        if response.fully_closed() {
+            self.open_sections.remove(key);
+        } else {
+            self.open_sections.insert(key.to_owned());
+        }
+    }
+
+    /// Throws away the image, contours and every parameter, and writes the
+    /// defaults to the config file. Saved settings are kept.
+    fn reset_to_defaults(&mut self, ctx: &egui::Context) {
+        let confirmed = rfd::MessageDialog::new()
+            .set_title(t!("reset_all"))
+            .set_description(t!("reset_confirm"))
+            .set_level(rfd::MessageLevel::Warning)
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show()
+            == rfd::MessageDialogResult::Yes;
+        if !confirmed {
+            return;
+        }
+        *self = Panel::default();
+        // Left unset when the write fails, so the next autosave tries again.
+        let config = self.config();
+        if config.save().is_ok() {
+            self.saved_config = Some(config);
+        }
+        self.apply_settings();
+        self.verify_screen_resolution();
+        set_locale(self.language);
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(window_level(
+            self.always_on_top,
+        )));
+        ctx.forget_all_images();
+    }
+
+    /// Saves what a draw would trace as an SVG, then hands it to the
+    /// configured editor.
+    fn export_svg(&self) {
+        let Some((width, height)) = self.resized_img.read().as_ref().map(|img| img.dimensions())
+        else {
+            return;
+        };
+        let center = *self.center.read();
+        let contours = {
+            let lines = self.lines.read();
+            let Some(lines) = lines.as_ref() else {
+                return;
+            };
+            let hatches = self.hatches.read();
+            let simplified = self.simplified.read();
+            image_space(
+                self.drawable(self.draw_source(lines, &simplified))
+                    .into_iter()
+                    .map(|(_, contour)| contour)
+                    .chain(hatches.iter()),
+                center,
+            )
+        };
+        let mut dialog = FileDialog::new()
+            .add_filter("SVG", &["svg"])
+            .set_file_name("drawing.svg");
+        if let Some(dir) = self.image_path.read().as_ref().and_then(|p| p.parent()) {
+            dialog = dialog.set_directory(dir);
+        }
+        let open_in = self.auto_open_svg_in.clone();
         rayon::spawn(move || {
-            let Some(path) = FileDialog::new()
-                .add_filter(
-                    "Image file",
-                    &[
-                        "avif", "jpg", "jpeg", "jfif", "png", "apng", "gif", "webp", "tif", "tiff",
-                        "tga", "dds", "bmp", "ico", "hdr", "exr", "pdm", "pam", "ppm", "pgm", "ff",
-                        "qoi", "pcx",
-                    ],
-                )
-                .pick_file()
-            else {
+            let Some(path) = dialog.save_file() else {
                 return;
             };
+            let result = export_svg(&contours, width, height, &path)
+                .map_err(|e| e.to_string())
+                .and_then(|_| match &open_in {
+                    Some(program) if !program.trim().is_empty() => Command::new(program.trim())
+                        .arg(&path)
+                        .spawn()
+                        .map(drop)
+                        .map_err(|e| format!("{program}: {e}")),
+                    _ => Ok(()),
+                });
+            if let Err(e) = result {
+                rfd::MessageDialog::new()
+                    .set_title("Error")
+                    .set_description(e)
+                    .show();
+            }
+        });
+    }
 
-            let Ok(mut image) = image::open(&path) else {
+    /// Saves the found contours and the delays as JSON, to be replayed with
+    /// [`Self::import_session`] without the image.
+    fn export_session(&self) {
+        let session = {
+            let lines = self.lines.read();
+            let Some(lines) = lines.as_ref() else {
+                return;
+            };
+            DrawingSession {
+                contours: lines.iter().map(Into::into).collect(),
+                min_points: self.min_points,
+                point_delay_us: self.point_delay_us,
+                contour_delay_ms: self.contour_delay_ms,
+                pen_travel_speed_px_per_us: self.pen_travel_speed_px_per_us,
+            }
+        };
+        let mut dialog = FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("session.json");
+        if let Some(dir) = self.image_path.read().as_ref().and_then(|p| p.parent()) {
+            dialog = dialog.set_directory(dir);
+        }
+        rayon::spawn(move || {
+            let Some(path) = dialog.save_file() else {
+                return;
+            };
+            if let Err(e) = save_session(&session, &path) {
                 rfd::MessageDialog::new()
                     .set_title("Error")
-                    .set_description("No image")
+                    .set_description(e.to_string())
                     .show();
+            }
+        });
+    }
+
+    /// Loads a saved session into `imported_session`, for the next frame to
+    /// pick up with [`Self::apply_session`].
+    fn import_session(&self) {
+        let imported = self.imported_session.clone();
+        rayon::spawn(move || {
+            let Some(path) = FileDialog::new().add_filter("JSON", &["json"]).pick_file() else {
+                return;
+            };
+            match load_session(&path) {
+                Ok(session) => *imported.write() = Some(session),
+                Err(e) => {
+                    rfd::MessageDialog::new()
+                        .set_title("Error")
+                        .set_description(e.to_string())
+                        .show();
+                }
+            }
+        });
+    }
+
+    /// Replaces the contours with a saved session's. They are already in
+    /// screen coordinates, so drawing goes on exactly as after a reload.
+    /// The image and everything worked out from it go, so a later reload
+    /// has nothing to re-extract and leaves the session's contours be.
+    fn apply_session(&mut self, ctx: &egui::Context, session: DrawingSession) {
+        self.min_points = session.min_points;
+        self.point_delay_us = session.point_delay_us;
+        self.contour_delay_ms = session.contour_delay_ms;
+        self.pen_travel_speed_px_per_us = session.pen_travel_speed_px_per_us;
+        self.raw_img.write().take();
+        self.resized_img.write().take();
+        self.image_path.write().take();
+        self.layers.write().clear();
+        // Screen coordinates are image coordinates with the image at the origin.
+        *self.center.write() = (0, 0);
+        self.palette.write().take();
+        self.detail.write().take();
+        self.depth.write().take();
+        self.gradient.write().take();
+        self.canny_image.write().take();
+        self.density.write().take();
+        self.comparison.write().take();
+        self.background.write().clear();
+        self.excluded.write().clear();
+        self.hatches.write().clear();
+        self.lines
+            .write()
+            .replace(session.contours.into_iter().map(Into::into).collect());
+        self.start_from.store(0);
+        self.resimplify();
+        ctx.forget_all_images();
+    }
+
+    /// Reloads at every low threshold from `sweep_start` to `sweep_end` and
+    /// saves what each would draw as a CSV, then goes back to the current
+    /// threshold. The estimate counts the point, contour and pen-up delays
+    /// only.
+    fn batch_calibration(&self, ctx: &egui::Context) {
+        let mut dialog = FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("calibration.csv");
+        if let Some(dir) = self.image_path.read().as_ref().and_then(|p| p.parent()) {
+            dialog = dialog.set_directory(dir);
+        }
+        self.sweeping.store(true);
+        // Reloads into buffers of its own, so the preview and a draw never
+        // see the swept contours.
+        let mut panel = Panel {
+            resized_img: Arc::new(RwLock::new(self.resized_img.read().clone())),
+            canny_image: Arc::new(RwLock::new(None)),
+            lines: Arc::new(RwLock::new(None)),
+            hatches: Arc::new(RwLock::new(vec![])),
+            simplified: Arc::new(RwLock::new(vec![])),
+            density: Arc::new(RwLock::new(None)),
+            measures: Arc::new(RwLock::new(Measures::default())),
+            detail: Arc::new(RwLock::new(None)),
+            depth: Arc::new(RwLock::new(None)),
+            gradient: Arc::new(RwLock::new(None)),
+            background: Arc::new(RwLock::new(vec![])),
+            excluded: Arc::new(RwLock::new(BTreeSet::new())),
+            palette: Arc::new(RwLock::new(None)),
+            start_from: Arc::new(AtomicCell::new(0)),
+            ..self.clone()
+        };
+        let sweeping = self.sweeping.clone();
+        let ctx = ctx.clone();
+        rayon::spawn(move || {
+            let Some(path) = dialog.save_file() else {
+                sweeping.store(false);
                 return;
             };
-            raw_img.write().replace(image.clone());
+            let mut csv = String::from("canny_value,contour_count,total_points,estimated_time\n");
+            let step = panel.sweep_step.max(1) as usize;
+            // Past the high threshold the low one would stop meaning anything.
+            let end = panel.sweep_end.min(panel.canny_high_value);
+            for value in (panel.sweep_start..=end).step_by(step) {
+                panel.canny_value = value;
+                panel.reload(false);
+                let (contours, travel) = {
+                    let lines = panel.lines.read();
+                    let simplified = panel.simplified.read();
+                    lines.as_ref().map_or((0, Duration::ZERO), |lines| {
+                        let drawable = panel.drawable(panel.draw_source(lines, &simplified));
+                        let travel = drawable
+                            .windows(2)
+                            .filter_map(|pair| {
+                                Some((pair[0].1.points.last()?, pair[1].1.points.first()?))
+                            })
+                            .map(|(from, to)| panel.pen_up_delay(*from, *to))
+                            .sum();
+                        (drawable.len(), travel)
+                    })
+                };
+                let points = panel.count_points();
+                let estimate = Duration::from_micros(panel.point_delay_us) * points as u32
+                    + Duration::from_millis(panel.contour_delay_ms) * contours as u32
+                    + travel;
+                let _ = writeln!(
+                    csv,
+                    "{value},{contours},{points},{:.1}",
+                    estimate.as_secs_f64()
+                );
+            }
+            sweeping.store(false);
+            ctx.request_repaint();
+            if let Err(e) = std::fs::write(&path, csv) {
+                rfd::MessageDialog::new()
+                    .set_title("Error")
+                    .set_description(e.to_string())
+                    .show();
+            }
+        });
+    }
+
+    /// Markdown summary of the loaded image, the settings and the last draw.
+    fn generate_report(&self) -> String {
+        let mut report = String::from("# AutoDraw report\n\n");
+        let image = match self.image_path.read().as_ref() {
+            Some(path) => path.display().to_string(),
+            None => "clipboard".to_string(),
+        };
+        let _ = writeln!(report, "- Image: `{image}`");
+        if let Some(resized) = self.resized_img.read().as_ref() {
+            let (width, height) = resized.dimensions();
+            let _ = writeln!(report, "- Drawn size: {width}×{height} px");
+        }
 
-            let dim = image.dimensions();
+        report.push_str("\n## Parameters\n\n");
+        let _ = writeln!(
+            report,
+            "- Canny: {} / {}",
+            self.canny_value, self.canny_high_value
+        );
+        let _ = writeln!(report, "- Area: {}%", self.area);
+        let _ = writeln!(report, "- Min points per contour: {}", self.min_points);
+        if self.max_points < usize::MAX {
+            let _ = writeln!(report, "- Max points per contour: {}", self.max_points);
+        }
+        let _ = writeln!(report, "- Point delay: {} µs", self.point_delay_us);
+        let _ = writeln!(report, "- Contour delay: {} ms", self.contour_delay_ms);
+        let _ = writeln!(
+            report,
+            "- Pen travel speed: {} px/µs",
+            self.pen_travel_speed_px_per_us
+        );
+        let _ = writeln!(report, "- Mode: {:?}", self.draw_mode);
 
-            let r = (
-                (SCREEN.0 as f32 * (area as f32 / 100.0)) as i32,
-                (SCREEN.1 as f32 * (area as f32 / 100.0)) as i32,
+        report.push_str("\n## Contours\n\n");
+        if let Some(lines) = self.lines.read().as_ref() {
+            let simplified = self.simplified.read();
+            let drawable = self.drawable(self.draw_source(lines, &simplified));
+            let points: usize = drawable.iter().map(|(_, c)| c.points.len()).sum();
+            let cost = drawing_cost(drawable.iter().map(|(_, contour)| *contour));
+            let _ = writeln!(report, "- Found: {}", lines.len());
+            let _ = writeln!(report, "- Drawable: {}", drawable.len());
+            let _ = writeln!(report, "- Points: {points}");
+            let _ = writeln!(
+                report,
+                "- Pen down / travel: {:.0} mm / {:.0} mm",
+                cost.stroke_mm, cost.travel_mm
             );
+        }
 
-            let rect = if (dim.1 as f32 / dim.0 as f32) < (2.0 / 3.0) {
-                r.0
-            } else {
-                r.1
-            };
+        report.push_str("\n## Last draw\n\n");
+        match self.last_draw_duration.load() {
+            Some(duration) => {
+                let _ = writeln!(report, "- Duration: {:.1} s", duration.as_secs_f64());
+            }
+            None => report.push_str("- Not drawn yet\n"),
+        }
+        let _ = writeln!(
+            report,
+            "- Strokes drawn: {}",
+            self.stroke_stats.read().len()
+        );
+        let _ = writeln!(
+            report,
+            "- Failed input events: {}",
+            self.input_errors.load()
+        );
 
-            image = image.resize(rect as _, rect as _, FilterType::Lanczos3);
-            let center = (
-                (SCREEN.0 - image.width() as i32) / 2,
-                (SCREEN.1 - image.height() as i32) / 2,
+        if let Some(preview) = self.canny_image.read().as_ref() {
+            let _ = writeln!(
+                report,
+                "\n## Preview\n\n![preview](data:image/png;base64,{})",
+                BASE64_STANDARD.encode(&preview.buf)
             );
-            *image_center.write() = center;
+        }
+        report
+    }
 
-            let gray = image.to_luma8();
-            resized_img.write().replace(image);
+    fn save_report(&self) {
+        let report = self.generate_report();
+        rayon::spawn(move || {
+            let Some(path) = FileDialog::new()
+                .add_filter("Markdown", &["md"])
+                .set_file_name("report.md")
+                .save_file()
+            else {
+                return;
+            };
+            if let Err(e) = std::fs::write(path, report) {
+                rfd::MessageDialog::new()
+                    .set_title("Error")
+                    .set_description(e.to_string())
+                    .show();
+            }
+        });
+    }
 
-            let mut data = Cursor::new(vec![]);
-            let mut contours = if !is_binary {
-                let canny = edges::canny(&gray, canny_value as f32, 3.0 * canny_value as f32);
-                canny.write_to(&mut data, image::ImageFormat::Png).ok();
-                contours::find_contours(&canny)
-            } else {
-                gray.write_to(&mut data, image::ImageFormat::Png).ok();
-                contours::find_contours(&gray)
+    /// Replays the last draw stroke by stroke onto a blank canvas and saves
+    /// a PNG after each one, `frame_00001.png` and on, for
+    /// `ffmpeg -i frame_%05d.png`. The strokes come from the draw's stroke
+    /// log, so the frames follow the order they were drawn in.
+    fn export_timelapse_png_sequence(&self) {
+        let Some((width, height)) = self.resized_img.read().as_ref().map(|img| img.dimensions())
+        else {
+            return;
+        };
+        let mut dialog = FileDialog::new();
+        if let Some(dir) = self.image_path.read().as_ref().and_then(|p| p.parent()) {
+            dialog = dialog.set_directory(dir);
+        }
+        let panel = self.clone();
+        rayon::spawn(move || {
+            let Some(dir) = dialog.pick_folder() else {
+                return;
             };
-            canny_image.write().replace(Img {
-                id: nanoid!(),
-                buf: data.into_inner(),
-            });
+            let center = *panel.center.read();
+            let strokes = {
+                let lines = panel.lines.read();
+                let Some(lines) = lines.as_ref() else {
+                    return;
+                };
+                let simplified = panel.simplified.read();
+                let hatches = panel.hatches.read();
+                // Stroke indices count the outlines first, then the hatches.
+                let source: Vec<&Contour<i32>> = panel
+                    .draw_source(lines, &simplified)
+                    .iter()
+                    .chain(hatches.iter())
+                    .collect();
+                let drawn = panel
+                    .stroke_stats
+                    .read()
+                    .iter()
+                    .filter_map(|stat| source.get(stat.index).copied())
+                    .collect::<Vec<_>>();
+                image_space(drawn, center)
+            };
+            let mut canvas = GrayImage::from_pixel(width, height, Luma([255]));
+            for (frame, stroke) in strokes.iter().enumerate() {
+                pattern::render_contour(&mut canvas, stroke, Luma([0]));
+                let path = dir.join(format!("frame_{:05}.png", frame + 1));
+                if let Err(e) = canvas.save(&path) {
+                    rfd::MessageDialog::new()
+                        .set_title("Error")
+                        .set_description(format!("{}: {e}", path.display()))
+                        .show();
+                    return;
+                }
+            }
+        });
+    }
 
-            contours.iter_mut().for_each(|contour| {
-                contour.points.iter_mut().for_each(|point| {
-                    point.x += center.0;
-                    point.y += center.1;
-                });
+    fn open_image(&self, mask: bool) {
+        let panel = self.clone();
+        rayon::spawn(move || {
+            let Some((path, image)) = pick_image() else {
+                return;
+            };
+            panel.set_image(path, image, mask);
+        });
+    }
+
+    /// Loads the first dropped file with a known image extension; the others
+    /// are only counted in a toast.
+    fn open_dropped(&mut self, ctx: &egui::Context, files: &[egui::DroppedFile]) {
+        let mut images = files
+            .iter()
+            .filter_map(|file| file.path.clone())
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| {
+                        IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+                    })
             });
-            lines.write().replace(contours);
+        let Some(path) = images.next() else {
+            self.toast = Some((t!("drop_unsupported").to_string(), Instant::now()));
+            return;
+        };
+        let ignored = files.len() - 1;
+        if ignored > 0 {
+            self.toast = Some((
+                t!("drop_ignored", value = ignored).to_string(),
+                Instant::now(),
+            ));
+        }
+        self.pre_crop = None;
+        let panel = self.clone();
+        let ctx = ctx.clone();
+        rayon::spawn(move || {
+            let Some(image) = read_image(&path) else {
+                return;
+            };
+            panel.set_image(path, image, false);
+            ctx.forget_all_images();
+            ctx.request_repaint();
         });
     }
 
-    fn resize(&self, mut image: DynamicImage) -> (i32, i32) {
-        let dim = image.dimensions();
+    fn set_image(&self, path: PathBuf, image: DynamicImage, mask: bool) {
+        self.image_path.write().replace(path);
+        self.layers.write().clear();
+        self.raw_img.write().replace(image);
+        self.is_mask.store(mask);
+        self.reload(true);
+    }
 
-        let r = (
-            (SCREEN.0 as f32 * (self.area as f32 / 100.0)) as i32,
-            (SCREEN.1 as f32 * (self.area as f32 / 100.0)) as i32,
-        );
+    fn add_layer(&self) {
+        let panel = self.clone();
+        rayon::spawn(move || {
+            let Some((path, image)) = pick_image() else {
+                return;
+            };
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            {
+                let mut layers = panel.layers.write();
+                if layers.len() >= MAX_LAYERS {
+                    return;
+                }
+                layers.push(Layer::new(name, image));
+            }
+            panel.apply_layers();
+        });
+    }
 
-        let rect = if (dim.1 as f32 / dim.0 as f32) < (2.0 / 3.0) {
-            r.0
-        } else {
-            r.1
+    fn passes_ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(t!("passes_hint"));
+        let mut remove = None;
+        for (index, pass) in self.passes.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}.", index + 1));
+                ui.add(
+                    egui::DragValue::new(&mut pass.canny_value)
+                        .range(1..=500)
+                        .prefix(t!("low_threshold")),
+                );
+                let mut key = pass.opacity_key.clone().unwrap_or_default();
+                ui.label(t!("opacity_key"));
+                if ui
+                    .add(egui::TextEdit::singleline(&mut key).desired_width(60.0))
+                    .changed()
+                {
+                    pass.opacity_key = (!key.trim().is_empty()).then_some(key);
+                }
+                ui.add(
+                    egui::DragValue::new(&mut pass.blend_delay_ms)
+                        .range(0..=60_000)
+                        .suffix("ms")
+                        .prefix(t!("blend_delay")),
+                );
+                if ui.button("🗑").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.passes.remove(index);
+        }
+        if ui.button(t!("add_pass")).clicked() {
+            self.passes.push(LayerConfig {
+                canny_value: self.canny_value,
+                opacity_key: None,
+                blend_delay_ms: 0,
+            });
+        }
+    }
+
+    fn apply_layers(&self) {
+        let Some(image) = composite(&self.layers.read()) else {
+            return;
         };
+        self.raw_img.write().replace(image);
+        self.is_mask.store(false);
+        self.reload(true);
+    }
+
+    fn layers_ui(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let mut changed = false;
+        let mut remove = None;
+        let mut layers = self.layers.write();
+        for (index, layer) in layers.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut layer.visible, &layer.name).changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0).text(t!("opacity")))
+                    .changed();
+                egui::ComboBox::from_id_salt(("blend", index))
+                    .selected_text(format!("{:?}", layer.blend))
+                    .show_ui(ui, |ui| {
+                        for mode in BlendMode::ALL {
+                            changed |= ui
+                                .selectable_value(&mut layer.blend, mode, format!("{mode:?}"))
+                                .changed();
+                        }
+                    });
+                if ui.button("🗑").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            layers.remove(index);
+            changed = true;
+        }
+        let full = layers.len() >= MAX_LAYERS;
+        drop(layers);
+
+        if ui
+            .add_enabled(!full, egui::Button::new(t!("add_layer")))
+            .clicked()
+        {
+            ctx.forget_all_images();
+            self.add_layer();
+        }
+        if changed {
+            ctx.forget_all_images();
+            self.apply_layers();
+        }
+    }
 
-        image = image.resize(rect as _, rect as _, FilterType::Lanczos3);
+    fn resize(&self, mut image: DynamicImage) -> (i32, i32) {
+        if let Some([x, y, width, height]) = self.pre_crop {
+            image = image.crop_imm(x, y, width, height);
+        }
+        // The box keeps the screen's proportions, `area` percent of it.
+        let (width, height) = (
+            ((SCREEN.0 as f32 * (self.area as f32 / 100.0)) as u32).max(1),
+            ((SCREEN.1 as f32 * (self.area as f32 / 100.0)) as u32).max(1),
+        );
+
+        let filter = self.filter_type.into();
+        image = match self.resize_mode {
+            ResizeMode::Fit => image.resize(width, height, filter),
+            ResizeMode::Fill => image.resize_to_fill(width, height, filter),
+            ResizeMode::Stretch => image.resize_exact(width, height, filter),
+        };
+        // Centered on whatever size the mode left the image at.
         let center = (
-            (SCREEN.0 - image.width() as i32) / 2,
-            (SCREEN.1 - image.height() as i32) / 2,
+            (SCREEN.0 - image.width() as i32) / 2 + self.offset_x,
+            (SCREEN.1 - image.height() as i32) / 2 + self.offset_y,
         );
 
         self.resized_img.write().replace(image);
@@ -220,170 +1412,3495 @@ impl Panel {
             return;
         };
         let center = *self.center.read();
-        let gray = resized_img.to_luma8();
-
-        let mut data = Cursor::new(vec![]);
-        let mut contours = if !self.is_binary {
-            let canny = edges::canny(
-                &gray,
-                self.canny_value as f32,
-                3.0 * self.canny_value as f32,
-            );
-            canny.write_to(&mut data, image::ImageFormat::Png).ok();
-            contours::find_contours(&canny)
+        let Trace { gray, edges: canny } =
+            self.trace(resized_img, self.canny_value, self.canny_high_value);
+        self.detail.write().replace(detail_map(&canny));
+        self.gradient.write().replace(gradient_magnitude(&gray));
+        *self.depth.write() = matches!(self.draw_mode, DrawMode::Stipple3D { .. })
+            .then(|| depth_map(&gray, self.depth_scale));
+        let (mut preview, mut contours, flags) = match self.draw_mode {
+            DrawMode::Spiral => {
+                let contours = spiral_scan(
+                    &gray,
+                    self.spiral_turns,
+                    self.spiral_spacing,
+                    self.darkness_threshold,
+                );
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::Voronoi => {
+                let contours = voronoi_stipple(&gray, self.voronoi_points);
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::Contourf => {
+                let contours = contourf(&gray, self.contourf_levels);
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::ConcentricCircles => {
+                let contours = concentric_circles(
+                    &gray,
+                    self.max_radius_px,
+                    self.ring_spacing_px,
+                    self.brightness_threshold,
+                );
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::Truchet => {
+                let contours = truchet(&gray, self.tile_size_px, self.seed);
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::KochSnowflake => {
+                let contours = koch_snowflake(
+                    gray.width(),
+                    gray.height(),
+                    self.koch_iterations,
+                    self.koch_size_px,
+                    self.koch_rotation,
+                );
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::Penrose => {
+                let contours = penrose(
+                    gray.width(),
+                    gray.height(),
+                    self.penrose_iterations,
+                    self.penrose_scale,
+                );
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::StringArt => {
+                let contours = string_art(&gray, self.peg_count, self.string_count);
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::ErrorDiffusionHalftone => {
+                let contours = error_diffusion(&gray);
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::Peano => {
+                let source = if !self.is_binary { &canny } else { &gray };
+                let contours = peano_scan(source, self.sampling_rate, MASK_THRESHOLD);
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::HilbertScan => {
+                let source = if !self.is_binary { &canny } else { &gray };
+                let contours = hilbert_path(
+                    &contours::find_contours(source),
+                    gray.width(),
+                    gray.height(),
+                );
+                let preview = pattern::render(&contours, gray.width(), gray.height());
+                (preview, contours, vec![])
+            }
+            DrawMode::Line | DrawMode::Stipple { .. } | DrawMode::Stipple3D { .. }
+                if self.is_mask.load() =>
+            {
+                // Masks are drawn black on white; the dark strokes are the foreground.
+                let preview = imageproc::map::map_colors(&gray, |p| {
+                    Luma([if p.0[0] < MASK_THRESHOLD { 255 } else { 0 }])
+                });
+                let contours = contours::find_contours(&preview);
+                (preview, contours, vec![])
+            }
+            DrawMode::Line | DrawMode::Stipple { .. } | DrawMode::Stipple3D { .. } => {
+                let mut preview = if !self.is_binary { canny } else { gray.clone() };
+                let contours = contours::find_contours(&preview);
+                let mask = background_mask(&gray);
+                let flags: Vec<bool> = contours.iter().map(|c| is_background(&mask, c)).collect();
+                contours
+                    .iter()
+                    .zip(&flags)
+                    .filter(|(_, flag)| **flag)
+                    .for_each(|(contour, _)| dim_contour(&mut preview, contour));
+                (preview, contours, flags)
+            }
+        };
+        let mut hatches = if self.hatch && !self.draw_mode.is_generated() {
+            let outlines: Vec<_> = contours
+                .iter()
+                .zip(flags.iter().chain(std::iter::repeat(&false)))
+                .filter(|(_, background)| !**background)
+                .map(|(contour, _)| contour.clone())
+                .collect();
+            hatch_fill(&outlines, self.hatch_angle, self.hatch_spacing)
         } else {
-            gray.write_to(&mut data, image::ImageFormat::Png).ok();
-            contours::find_contours(&gray)
+            vec![]
         };
-        self.canny_image.write().replace(Img {
-            id: nanoid!(),
-            buf: data.into_inner(),
+        for hatch in &hatches {
+            for pair in hatch.points.windows(2) {
+                draw_line_segment_mut(
+                    &mut preview,
+                    (pair[0].x as f32, pair[0].y as f32),
+                    (pair[1].x as f32, pair[1].y as f32),
+                    Luma([128]),
+                );
+            }
+        }
+        *self.palette.write() = self.palette_mapping.then(|| {
+            Palette::new(
+                &resized_img.to_rgb8(),
+                self.palette_size,
+                contours.iter().chain(&hatches),
+            )
         });
+        self.background.write().clone_from(&flags);
+        self.canny_image
+            .write()
+            .replace(Img::encode(preview.into()));
+        let density = density_map(&contours, gray.width(), gray.height());
+        self.density.write().replace((
+            Img::encode(density.heatmap.into()),
+            density.mean,
+            density.peak,
+        ));
 
-        contours.iter_mut().for_each(|contour| {
-            contour.points.iter_mut().for_each(|point| {
-                point.x += center.0;
-                point.y += center.1;
+        contours
+            .iter_mut()
+            .chain(hatches.iter_mut())
+            .for_each(|contour| {
+                contour.points.iter_mut().for_each(|point| {
+                    point.x += center.0;
+                    point.y += center.1;
+                });
             });
-        });
         self.lines.write().replace(contours);
+        self.excluded.write().clear();
+        self.start_from.store(0);
+        *self.hatches.write() = hatches;
+        self.resimplify();
     }
 
-    fn draw(&self) {
-        let contours = self.lines.clone();
-        let point_count = self.point_count;
-        rayon::spawn(move || {
-            STATE.store(State::Drawing);
-            DRAWING.store(true);
-            let contours = contours.read();
-            let Some(contours) = contours.as_ref() else {
+    /// Runs the image through the gray filters, the blur, the edge detector
+    /// and the edge cleanup. Reloads, multi-pass draws and reference
+    /// comparisons all trace through here, so they agree on the edges.
+    fn trace(&self, image: &DynamicImage, low: u32, high: u32) -> Trace {
+        let gray = self.prepare_gray(image);
+        let blurred = (self.blur_sigma > 0.0).then(|| imageops::blur(&gray, self.blur_sigma));
+        let edges = detect_edges(
+            blurred.as_ref().unwrap_or(&gray),
+            self.edge_method,
+            low,
+            high,
+        );
+        let edges = postprocess_edges(&edges, self.dilate_iters, self.erode_iters);
+        Trace { gray, edges }
+    }
+
+    /// The line contours of a trace as a draw would visit them: past the
+    /// shape filters and simplified. Background and hand exclusions are left
+    /// out, as they are picked per contour of the reloaded image.
+    fn trace_contours(&self, trace: &Trace) -> Vec<Contour<i32>> {
+        let source = if !self.is_binary {
+            &trace.edges
+        } else {
+            &trace.gray
+        };
+        contours::find_contours::<i32>(source)
+            .into_iter()
+            .filter(|contour| self.keeps_shape(contour))
+            .map(|contour| {
+                if self.draw_mode == DrawMode::Line && self.simplification_epsilon > 0.0 {
+                    let points = simplify_contour(&contour, self.simplification_epsilon);
+                    Contour::new(points, contour.border_type, contour.parent)
+                } else {
+                    contour
+                }
+            })
+            .collect()
+    }
+
+    /// The grayscale image edges are found in, after inversion and the
+    /// noise and sharpening filters.
+    fn prepare_gray(&self, image: &DynamicImage) -> GrayImage {
+        let brightened;
+        let image = if self.brightness != 0 {
+            brightened = image.brighten(self.brightness);
+            &brightened
+        } else {
+            image
+        };
+        let gray = if self.invert_colors {
+            let mut inverted = image.clone();
+            inverted.invert();
+            inverted.to_luma8()
+        } else {
+            image.to_luma8()
+        };
+        // Contrast is about mid-gray, so it doesn't matter that it comes after inverting.
+        let gray = if (self.contrast - 1.0).abs() > f32::EPSILON {
+            adjust_contrast(&gray, self.contrast)
+        } else {
+            gray
+        };
+        // Knocks out scan speckle before it turns into false edges.
+        let gray = if self.median_radius > 0 {
+            median_filter(&gray, self.median_radius, self.median_radius)
+        } else {
+            gray
+        };
+        // Smooths out texture in flat areas while leaving the detail sharp.
+        let gray = adaptive_blur(&gray, self.adaptive_blur_sigma);
+        // Unsharp mask brings soft edges back up over the Canny threshold.
+        if self.unsharp_mask_sigma > 0.0 && self.unsharp_amount > 0.0 {
+            sharpen_gaussian(&gray, self.unsharp_mask_sigma, self.unsharp_amount)
+        } else {
+            gray
+        }
+    }
+
+    fn resimplify(&self) {
+        let simplified = self
+            .lines
+            .read()
+            .iter()
+            .flatten()
+            .map(|contour| {
+                Contour::new(
+                    simplify_contour(contour, self.simplification_epsilon),
+                    contour.border_type,
+                    contour.parent,
+                )
+            })
+            .collect();
+        *self.simplified.write() = simplified;
+        self.measure();
+    }
+
+    fn measure_key(&self) -> MeasureKey {
+        MeasureKey {
+            draw_mode: self.draw_mode,
+            skip_background: self.skip_background,
+            excluded: self.excluded.read().len(),
+            min_points: self.min_points,
+            max_points: self.max_points,
+            min_bounding_radius_px: self.min_bounding_radius_px,
+            min_area: self.min_area,
+            simplification_epsilon: self.simplification_epsilon,
+            show_coverage: self.show_coverage,
+            coverage_grid: self.coverage_grid,
+        }
+    }
+
+    /// Refreshes the cached drawable count, cost, overlap and coverage.
+    fn measure(&self) {
+        let measures = match self.lines.read().as_ref() {
+            Some(lines) => {
+                let simplified = self.simplified.read();
+                let hatches = self.hatches.read();
+                let drawable = self.drawable(self.draw_source(lines, &simplified));
+                Measures {
+                    drawable: drawable.len(),
+                    cost: drawing_cost(
+                        drawable
+                            .iter()
+                            .map(|(_, contour)| *contour)
+                            .chain(hatches.iter()),
+                    ),
+                    overlap: self.compute_stroke_overlap(),
+                    coverage: self
+                        .show_coverage
+                        .then(|| self.compute_contour_density_map())
+                        .flatten(),
+                }
+            }
+            None => Measures::default(),
+        };
+        *self.measures.write() = measures;
+    }
+
+    /// Applies the built-in profile for the target window's app, if it is a
+    /// known one. Picking another profile afterwards overrides it.
+    fn detect_profile(&mut self) {
+        let Some(profile) = self.target_window.as_deref().and_then(detect_profile) else {
+            return;
+        };
+        self.toast = Some((
+            t!("profile_detected", name = profile.name).to_string(),
+            Instant::now(),
+        ));
+        self.apply_profile(&profile);
+    }
+
+    fn apply_profile(&mut self, profile: &AppProfile) {
+        self.point_delay_us = profile.per_point_delay_us;
+        self.pen_travel_speed_px_per_us = profile.pen_travel_speed_px_per_us;
+        self.pre_stroke_keys.clone_from(&profile.pre_stroke_keys);
+        self.backend = profile.backend;
+        self.color_picker_shortcut
+            .clone_from(&profile.color_picker_shortcut);
+        if let DrawMode::Stipple { min_spacing_px } | DrawMode::Stipple3D { min_spacing_px } =
+            &mut self.draw_mode
+        {
+            *min_spacing_px = profile.brush_radius as u32 * 2;
+        }
+        self.profile = Some(profile.name.clone());
+        self.check_backend();
+    }
+
+    /// The synthetic pen needs Windows 10 1809 or later. Without it draws
+    /// fall back to the mouse, so the selector is switched to match.
+    fn check_backend(&mut self) {
+        if self.backend == Backend::VirtualTablet && Pen::new().is_err() {
+            self.backend = Backend::Mouse;
+            self.toast = Some((t!("pen_unavailable").to_string(), Instant::now()));
+        }
+    }
+
+    /// Returns the current value of the parameter `param` refers to.
+    fn param_value(&self, param: Param) -> Param {
+        match param {
+            Param::Canny(_) => Param::Canny(self.canny_value),
+            Param::CannyHigh(_) => Param::CannyHigh(self.canny_high_value),
+            Param::Area(_) => Param::Area(self.area),
+            Param::Simplify(_) => Param::Simplify(self.simplification_epsilon),
+        }
+    }
+
+    fn apply_param(&mut self, ctx: &egui::Context, param: Param) {
+        ctx.forget_all_images();
+        match param {
+            Param::Canny(value) => {
+                self.canny_value = value;
+                self.reload(false);
+            }
+            Param::CannyHigh(value) => {
+                self.canny_high_value = value;
+                self.reload(false);
+            }
+            Param::Area(value) => {
+                self.area = value;
+                self.reload(true);
+            }
+            Param::Simplify(value) => {
+                self.simplification_epsilon = value;
+                self.resimplify();
+            }
+        }
+    }
+
+    /// A drag records one step for where it started rather than one per frame.
+    fn record_change(&mut self, response: &egui::Response, before: Param) {
+        if response.drag_started() || (response.changed() && !response.dragged()) {
+            self.history.record(before);
+        }
+    }
+
+    fn undo(&mut self, ctx: &egui::Context) {
+        let mut history = std::mem::take(&mut self.history);
+        let param = history.undo(|param| self.param_value(param));
+        self.history = history;
+        if let Some(param) = param {
+            self.apply_param(ctx, param);
+        }
+    }
+
+    fn redo(&mut self, ctx: &egui::Context) {
+        let mut history = std::mem::take(&mut self.history);
+        let param = history.redo(|param| self.param_value(param));
+        self.history = history;
+        if let Some(param) = param {
+            self.apply_param(ctx, param);
+        }
+    }
+
+    /// Trims blank borders: each edge moves inward until a row or column
+    /// varies by more than `CROP_VARIANCE`. Works on the resized image and
+    /// maps the result back onto the source.
+    fn smart_crop(&mut self, ctx: &egui::Context) {
+        let Some((raw_width, raw_height)) = self.raw_img.read().as_ref().map(|i| i.dimensions())
+        else {
+            return;
+        };
+        let Some(gray) = self.resized_img.read().as_ref().map(|i| i.to_luma8()) else {
+            return;
+        };
+        let (width, height) = gray.dimensions();
+        let has_content = |pixels: &mut dyn Iterator<Item = u8>| {
+            let values: Vec<f32> = pixels.map(f32::from).collect();
+            let mean = values.iter().sum::<f32>() / values.len().max(1) as f32;
+            let variance =
+                values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len().max(1) as f32;
+            variance > CROP_VARIANCE
+        };
+        let row = |y: u32| has_content(&mut (0..width).map(|x| gray.get_pixel(x, y).0[0]));
+        let column = |x: u32| has_content(&mut (0..height).map(|y| gray.get_pixel(x, y).0[0]));
+        let (Some(top), Some(bottom), Some(left), Some(right)) = (
+            (0..height).find(|&y| row(y)),
+            (0..height).rev().find(|&y| row(y)),
+            (0..width).find(|&x| column(x)),
+            (0..width).rev().find(|&x| column(x)),
+        ) else {
+            return;
+        };
+
+        // The resized image may already be a crop of the source.
+        let [x, y, source_width, source_height] =
+            self.pre_crop.unwrap_or([0, 0, raw_width, raw_height]);
+        let mut scale = (
+            source_width as f32 / width as f32,
+            source_height as f32 / height as f32,
+        );
+        // Fill crops the long side evenly, so that side starts further in.
+        let mut margin = (0.0, 0.0);
+        if self.resize_mode == ResizeMode::Fill {
+            let uniform = scale.0.min(scale.1);
+            margin = (
+                (source_width as f32 - width as f32 * uniform) / 2.0,
+                (source_height as f32 - height as f32 * uniform) / 2.0,
+            );
+            scale = (uniform, uniform);
+        }
+        let crop = [
+            x + (margin.0 + left as f32 * scale.0) as u32,
+            y + (margin.1 + top as f32 * scale.1) as u32,
+            (((right - left + 1) as f32 * scale.0) as u32).max(1),
+            (((bottom - top + 1) as f32 * scale.1) as u32).max(1),
+        ];
+        self.pre_crop = Some(crop);
+        ctx.forget_all_images();
+        self.reload(true);
+    }
+
+    /// Hides AutoDraw, screenshots where the drawing went and lines the
+    /// screenshot up with the reference, so the comparison window can show
+    /// which edges made it onto the canvas.
+    fn compare_to_reference(&self, ctx: &egui::Context) {
+        let Some(reference) = self.resized_img.read().clone() else {
+            return;
+        };
+        // Registered against the edges, as those are what got drawn.
+        let edges = self
+            .trace(&reference, self.canny_value, self.canny_high_value)
+            .edges;
+        let (x, y) = *self.center.read();
+        let (width, height) = reference.dimensions();
+        let region = ScreenRect {
+            x,
+            y,
+            width,
+            height,
+        };
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        let comparison = self.comparison.clone();
+        let ctx = ctx.clone();
+        rayon::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            let captured = capture_screen(region);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            if let Some(drawn) = captured {
+                // Strokes are dark on the canvas but bright in the edge map.
+                let mut strokes = DynamicImage::ImageRgba8(drawn.clone()).to_luma8();
+                image::imageops::invert(&mut strokes);
+                let shift = phase_correlation(&edges, &strokes);
+                let aligned = imageproc::geometric_transformations::translate(&drawn, shift);
+                *comparison.write() = Some(Comparison {
+                    reference: Img::encode(reference),
+                    drawn: Img::encode(aligned.into()),
+                    size: [width, height],
+                });
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Hides AutoDraw, screenshots the desktop and looks for the blank canvas.
+    fn detect_area(&self, ctx: &egui::Context) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        let detected = self.detected_canvas.clone();
+        let ctx = ctx.clone();
+        rayon::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            let screen = capture_screen(ScreenRect {
+                x: 0,
+                y: 0,
+                width: SCREEN.0 as u32,
+                height: SCREEN.1 as u32,
+            });
+            *detected.write() = screen.as_ref().and_then(detect_canvas);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Hides the window, lets the user drag out a rectangle on the screen
+    /// and loads what's inside it as the image. Esc cancels.
+    #[cfg(target_os = "windows")]
+    fn capture_region(&mut self, ctx: &egui::Context) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        self.pre_crop = None;
+        let panel = self.clone();
+        let ctx = ctx.clone();
+        rayon::spawn(move || {
+            let captured = select_region().and_then(capture_screen);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            if let Some(image) = captured {
+                panel.layers.write().clear();
+                panel.image_path.write().take();
+                panel
+                    .raw_img
+                    .write()
+                    .replace(DynamicImage::ImageRgba8(image));
+                panel.is_mask.store(false);
+                panel.reload(true);
+                ctx.forget_all_images();
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Waits for the next left click anywhere on screen and centers the
+    /// drawing on it.
+    fn center_on_click(&self) {
+        let (centering, picked) = (self.centering.clone(), self.picked_center.clone());
+        centering.store(true);
+        rayon::spawn(move || {
+            // Let go of the button that started this first.
+            while is_pressed(VK_LBUTTON.0) {
+                thread::sleep(Duration::from_millis(10));
+            }
+            while !is_pressed(VK_LBUTTON.0) {
+                if is_pressed(VK_ESCAPE.0) {
+                    centering.store(false);
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            picked.store(cursor_pos());
+            centering.store(false);
+        });
+    }
+
+    /// Finds the drawable canvas with test marks: clicks at the corners and
+    /// center of the current canvas (or the screen), looks for where each
+    /// mark showed up and takes it back with Ctrl+Z. The marks set the canvas,
+    /// and with it the offset, plus the input scale.
+    fn auto_scale_to_window(&self, ctx: &egui::Context) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        let panel = self.clone();
+        let ctx = ctx.clone();
+        let area = self.canvas.unwrap_or(ScreenRect {
+            x: 0,
+            y: 0,
+            width: SCREEN.0 as u32,
+            height: SCREEN.1 as u32,
+        });
+        rayon::spawn(move || {
+            thread::sleep(Duration::from_millis(500));
+            let probes = probe_points(area);
+            let mut input = Input::new(panel.backend, 0.0);
+            let marks = probes.map(|probe| sample_mark(&mut input, probe));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+            match canvas_from_marks(&probes, &marks) {
+                Some((rect, scale)) => {
+                    panel.px_per_screen_unit.store(scale);
+                    let mut settings = load_settings();
+                    settings.px_per_screen_unit = Some(scale);
+                    settings.save().ok();
+                    *panel.detected_canvas.write() = Some(rect);
+                }
+                None => {
+                    let found = marks.iter().flatten().count();
+                    rfd::MessageDialog::new()
+                        .set_title(t!("sample_canvas"))
+                        .set_description(t!("sample_canvas_failed", value = found))
+                        .set_level(rfd::MessageLevel::Warning)
+                        .show();
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn apply_canvas(&mut self, ctx: &egui::Context, rect: ScreenRect) {
+        let wide = self
+            .raw_img
+            .read()
+            .as_ref()
+            .map(|image| {
+                let dim = image.dimensions();
+                (dim.1 as f32 / dim.0 as f32) < (2.0 / 3.0)
+            })
+            .unwrap_or(false);
+        let base = if wide { SCREEN.0 } else { SCREEN.1 };
+        let side = rect.width.min(rect.height) as f32;
+        self.area = (side / base as f32 * 100.0).floor().clamp(1.0, 100.0) as u32;
+        let (x, y) = rect.center();
+        self.offset_x = x - SCREEN.0 / 2;
+        self.offset_y = y - SCREEN.1 / 2;
+        self.canvas = Some(rect);
+        self.show_overlay = true;
+        ctx.forget_all_images();
+        self.reload(true);
+    }
+
+    fn just_pressed(&mut self, vk: u16) -> bool {
+        let pressed = is_pressed(vk);
+        let held = self.held_keys.contains(&vk);
+        if pressed && !held {
+            self.held_keys.push(vk);
+        } else if !pressed && held {
+            self.held_keys.retain(|key| *key != vk);
+        }
+        pressed && !held
+    }
+
+    /// Binds `hotkey` to the first key held down, or gives up on Esc, and
+    /// lets the config autosave pick it up. Keys already in use are turned
+    /// down.
+    fn rebind(&mut self, hotkey: Hotkey) {
+        // Mouse buttons sit below 0x08, so the click on the button is ignored.
+        let Some(vk) = (0x08..=0xFE).find(|vk| is_pressed(*vk)) else {
+            return;
+        };
+        self.rebinding = None;
+        if vk == VK_ESCAPE.0 {
+            return;
+        }
+        // The new key is held right now; it shouldn't fire straight away.
+        self.held_keys.push(vk);
+        let taken = Hotkey::ALL
+            .into_iter()
+            .any(|other| other != hotkey && self.hotkeys.get(other) == vk);
+        // F3/F4 step the threshold while idle, so they can only share with
+        // keys that act during a draw.
+        let reserved =
+            matches!(hotkey, Hotkey::Start | Hotkey::Stop) && [VK_F3.0, VK_F4.0].contains(&vk);
+        if taken || reserved {
+            self.toast = Some((
+                t!("hotkey_taken", key = key_name(vk)).to_string(),
+                Instant::now(),
+            ));
+            return;
+        }
+        *self.hotkeys.get_mut(hotkey) = vk;
+    }
+
+    fn step_canny(&mut self, ctx: &egui::Context, increase: bool) {
+        self.history.record(Param::Canny(self.canny_value));
+        self.canny_value = if increase {
+            self.canny_value.saturating_add(CANNY_STEP)
+        } else {
+            self.canny_value.saturating_sub(CANNY_STEP)
+        }
+        .clamp(1, self.canny_high_value.clamp(1, 500));
+        self.toast = Some((
+            t!("canny_toast", value = self.canny_value).to_string(),
+            Instant::now(),
+        ));
+        ctx.forget_all_images();
+        self.reload(false);
+    }
+
+    /// Points in the contours a draw would visit, hatches included.
+    /// Multi-pass draws add theirs as each pass is traced.
+    fn count_points(&self) -> usize {
+        if self.multi_pass() {
+            return 0;
+        }
+        let lines = self.lines.read();
+        let Some(lines) = lines.as_ref() else {
+            return 0;
+        };
+        let simplified = self.simplified.read();
+        let drawable = self.drawable(self.draw_source(lines, &simplified));
+        let start = self.start_from.load().min(drawable.len());
+        let end = self
+            .max_contours
+            .map_or(drawable.len(), |max| (start + max).min(drawable.len()));
+        drawable[start..end]
+            .iter()
+            .map(|(_, contour)| contour.points.len())
+            .chain(self.hatches.read().iter().map(|hatch| hatch.points.len()))
+            .sum()
+    }
+
+    fn draw(&self, ctx: &egui::Context) {
+        self.total_points.store(self.count_points());
+        self.drawn_points.store(0);
+        self.draw_started.store(Some(Instant::now()));
+        let panel = self.clone();
+        let ctx = ctx.clone();
+        if panel.hide_during_draw {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        }
+        rayon::spawn(move || {
+            let finished = AtomicCell::new(false);
+            thread::scope(|scope| {
+                // A minimized window stops polling the hotkeys, so the keys
+                // that act during a draw are watched here, and only here.
+                scope.spawn(|| {
+                    let (mut pause_held, mut skip_held) = (false, false);
+                    while !finished.load() {
+                        if is_pressed(panel.hotkeys.stop) {
+                            STATE.store(State::Stop);
+                        }
+                        let pause = is_pressed(panel.hotkeys.pause);
+                        if pause && !pause_held {
+                            toggle_pause();
+                        }
+                        pause_held = pause;
+                        let skip = is_pressed(panel.hotkeys.skip);
+                        if skip && !skip_held {
+                            SKIP.store(true);
+                        }
+                        skip_held = skip;
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                });
+                let started = Instant::now();
+                if panel.multi_pass() {
+                    panel.run_passes();
+                } else {
+                    panel.run_draw();
+                }
+                panel.last_draw_duration.store(Some(started.elapsed()));
+                finished.store(true);
+            });
+            if panel.hide_during_draw {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn run_draw(&self) {
+        STATE.store(State::Drawing);
+        DRAWING.store(true);
+        SKIP.store(false);
+        let lines = self.lines.read();
+        let Some(contours) = lines.as_ref() else {
+            STATE.store(State::Stop);
+            DRAWING.store(false);
+            return;
+        };
+        // Hatch strokes are numbered after the outlines in the statistics.
+        let hatch_offset = contours.len();
+
+        let simplified = self.simplified.read();
+        let mut contours = self.drawable(self.draw_source(contours, &simplified));
+        self.order_contours(&mut contours);
+        self.group_by_color(&mut contours);
+        let warped = self.apply_warps(&contours);
+        let contours = match &warped {
+            Some(warped) => contours
+                .iter()
+                .zip(warped)
+                .map(|((index, _), contour)| (*index, contour))
+                .collect(),
+            None => contours,
+        };
+        self.invalid_point_count.store(0);
+        let validated = self.validate_contours(&contours);
+        let contours = match &validated {
+            Some(validated) => contours
+                .iter()
+                .zip(validated)
+                .map(|((index, _), contour)| (*index, contour))
+                .collect(),
+            None => contours,
+        };
+        // A capped draw picks up where the last one stopped.
+        let total = contours.len();
+        let start = self.start_from.load().min(total);
+        let end = self
+            .max_contours
+            .map_or(total, |max| (start + max).min(total));
+        let contours = contours[start..end].to_vec();
+        self.stroke_stats.write().clear();
+        self.stats_from_passes.store(false);
+        self.input_errors.store(0);
+
+        let target = self.draw_center();
+        let mut enigo = Enigo::new(&Settings::default()).unwrap();
+        if !verify_injection(&mut enigo, target)
+            && rfd::MessageDialog::new()
+                .set_title(t!("injection_title"))
+                .set_description(t!("injection_warning"))
+                .set_level(rfd::MessageLevel::Warning)
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show()
+                != rfd::MessageDialogResult::Yes
+        {
+            STATE.store(State::Stop);
+            DRAWING.store(false);
+            return;
+        }
+
+        if self.show_progress_border && start == 0 {
+            let border = border_contours(self.draw_extent(), self.border_width);
+            let border: Vec<_> = border.iter().enumerate().collect();
+            let mut input = Input::new(self.backend, self.pressure_noise_amplitude);
+            self.draw_contours(&mut input, &border);
+            self.stroke_stats.write().clear();
+        }
+
+        if self.thumbnail_first && start == 0 {
+            let thumbnail = thumbnail_contours(&contours, self.draw_center());
+            let thumbnail: Vec<_> = thumbnail.iter().enumerate().collect();
+            let mut input = Input::new(self.backend, self.pressure_noise_amplitude);
+            self.draw_contours(&mut input, &thumbnail);
+            self.stroke_stats.write().clear();
+            if !self.await_confirmation() {
+                STATE.store(State::Stop);
+                DRAWING.store(false);
+                return;
+            }
+        }
+
+        let chunk_size = contours.len().div_ceil(self.chunks.max(1) as usize).max(1);
+        for (chunk, part) in contours.chunks(chunk_size).enumerate() {
+            if chunk > 0 && !self.chunk_pause(chunk) {
+                break;
+            }
+            let groups = split_quadrants(part, self.parallel_workers);
+            // Workers block on the barrier, so they get dedicated threads rather than
+            // rayon tasks that could starve each other on a small pool.
+            let barrier = Barrier::new(groups.len());
+            thread::scope(|scope| {
+                for group in &groups {
+                    let barrier = &barrier;
+                    scope.spawn(move || {
+                        let mut input = Input::new(self.backend, self.pressure_noise_amplitude);
+                        barrier.wait();
+                        self.draw_contours(&mut input, group);
+                    });
+                }
+            });
+        }
+        if wait_while_paused() {
+            if end < total {
+                self.start_from.store(end);
                 STATE.store(State::Stop);
+                DRAWING.store(false);
                 return;
+            }
+            self.start_from.store(0);
+        }
+
+        // Hatching goes over the finished outlines as a second pass.
+        let hatches = self.hatches.read();
+        if !hatches.is_empty() && wait_while_paused() {
+            let mut hatches: Vec<_> = hatches
+                .iter()
+                .enumerate()
+                .map(|(index, hatch)| (hatch_offset + index, hatch))
+                .collect();
+            self.group_by_color(&mut hatches);
+            let warped = self.apply_warps(&hatches);
+            let hatches = match &warped {
+                Some(warped) => warped
+                    .iter()
+                    .enumerate()
+                    .map(|(index, hatch)| (hatch_offset + index, hatch))
+                    .collect(),
+                None => hatches,
+            };
+            let validated = self.validate_contours(&hatches);
+            let hatches = match &validated {
+                Some(validated) => validated
+                    .iter()
+                    .enumerate()
+                    .map(|(index, hatch)| (hatch_offset + index, hatch))
+                    .collect(),
+                None => hatches,
             };
+            let mut input = Input::new(self.backend, self.pressure_noise_amplitude);
+            self.draw_contours(&mut input, &hatches);
+        }
+        STATE.store(State::Stop);
+        DRAWING.store(false);
+    }
 
-            let mut enigo = Enigo::new(&Settings::default()).unwrap();
+    fn multi_pass(&self) -> bool {
+        !self.passes.is_empty() && self.draw_mode == DrawMode::Line && !self.is_mask.load()
+    }
 
-            for contour in contours.iter() {
-                if let State::Stop = STATE.load() {
-                    enigo
-                        .button(enigo::Button::Left, enigo::Direction::Release)
-                        .ok();
-                    break;
+    /// Traces the image once per pass, each at its own Canny threshold,
+    /// waiting out the pass's blend delay and tapping its opacity key first.
+    /// Palette mapping, the stroke map and the time-lapse are left out: the
+    /// pass contours aren't the ones they index.
+    fn run_passes(&self) {
+        STATE.store(State::Drawing);
+        DRAWING.store(true);
+        SKIP.store(false);
+        let image = self.resized_img.read().clone();
+        let Some(image) = image else {
+            STATE.store(State::Stop);
+            DRAWING.store(false);
+            return;
+        };
+        let center = *self.center.read();
+        let panel = Panel {
+            palette: Arc::new(RwLock::new(None)),
+            ..self.clone()
+        };
+        self.stroke_stats.write().clear();
+        self.stats_from_passes.store(true);
+        self.input_errors.store(0);
+        let mut input = Input::new(self.backend, self.pressure_noise_amplitude);
+
+        for pass in &self.passes {
+            thread::sleep(Duration::from_millis(pass.blend_delay_ms as u64));
+            if !wait_while_paused() {
+                break;
+            }
+            if let Some(keys) = pass.opacity_key.as_deref().and_then(parse_shortcut) {
+                input.chord(&keys);
+            }
+            let trace = self.trace(
+                &image,
+                pass.canny_value,
+                self.canny_high_value.max(pass.canny_value),
+            );
+            let mut lines = self.trace_contours(&trace);
+            lines.iter_mut().for_each(|contour| {
+                contour.points.iter_mut().for_each(|point| {
+                    point.x += center.0;
+                    point.y += center.1;
+                });
+            });
+            let mut lines: Vec<_> = lines.iter().enumerate().collect();
+            self.total_points
+                .fetch_add(lines.iter().map(|(_, contour)| contour.points.len()).sum());
+            self.order_contours(&mut lines);
+            let warped = self.apply_warps(&lines);
+            let lines = match &warped {
+                Some(warped) => lines
+                    .iter()
+                    .zip(warped)
+                    .map(|((index, _), contour)| (*index, contour))
+                    .collect(),
+                None => lines,
+            };
+            let validated = self.validate_contours(&lines);
+            let lines = match &validated {
+                Some(validated) => lines
+                    .iter()
+                    .zip(validated)
+                    .map(|((index, _), contour)| (*index, contour))
+                    .collect(),
+                None => lines,
+            };
+            panel.draw_contours(&mut input, &lines);
+        }
+        STATE.store(State::Stop);
+        DRAWING.store(false);
+    }
+
+    /// Clamps points that land off the screen, which offsets and warps can
+    /// cause, and counts them in `invalid_point_count`. The fixed copies come
+    /// back only when something had to change.
+    fn validate_contours(&self, contours: &[(usize, &Contour<i32>)]) -> Option<Vec<Contour<i32>>> {
+        let (width, height) = *SCREEN;
+        let valid = |p: &Point<i32>| (0..=width).contains(&p.x) && (0..=height).contains(&p.y);
+        if contours
+            .iter()
+            .all(|(_, contour)| contour.points.iter().all(valid))
+        {
+            return None;
+        }
+        let mut invalid = 0;
+        let fixed = contours
+            .iter()
+            .map(|(_, contour)| {
+                invalid += contour.points.iter().filter(|p| !valid(p)).count();
+                let points = contour
+                    .points
+                    .iter()
+                    .map(|p| Point::new(p.x.clamp(0, width), p.y.clamp(0, height)))
+                    .collect();
+                Contour::new(points, contour.border_type, contour.parent)
+            })
+            .collect();
+        self.invalid_point_count.fetch_add(invalid);
+        Some(fixed)
+    }
+
+    fn order_contours(&self, contours: &mut [(usize, &Contour<i32>)]) {
+        if self.optimize_order {
+            let refs: Vec<&Contour<i32>> = contours.iter().map(|(_, contour)| *contour).collect();
+            let original = contours.to_vec();
+            for (slot, index) in contours
+                .iter_mut()
+                .zip(reorder_contours_nearest_neighbour(&refs))
+            {
+                *slot = original[index];
+            }
+            return;
+        }
+        match self.draw_order {
+            DrawOrder::Original => {}
+            DrawOrder::Importance => {
+                if let Some(gradient) = self.gradient.read().as_ref() {
+                    self.contour_score
+                        .sort(contours, gradient, *self.center.read());
+                }
+            }
+            order => order.sort_by_position(contours),
+        }
+    }
+
+    /// With palette mapping on, draws one color at a time in the palette's
+    /// order. The sort is stable, so each color keeps the order it had.
+    fn group_by_color(&self, contours: &mut [(usize, &Contour<i32>)]) {
+        if let Some(palette) = self.palette.read().as_ref() {
+            contours.sort_by_key(|(index, _)| palette.rank(*index));
+        }
+    }
+
+    /// Opens the app's color picker with its shortcut and pastes `color` in
+    /// as hex through the clipboard.
+    fn select_color(&self, input: &mut Input, color: [u8; 3]) {
+        let Some(keys) = parse_shortcut(&self.color_picker_shortcut) else {
+            return;
+        };
+        let Ok(mut clipboard) = Clipboard::new() else {
+            return;
+        };
+        if clipboard.set_text(hex(color)).is_err() {
+            return;
+        }
+        input.chord(&keys);
+        thread::sleep(PICKER_DELAY);
+        input.chord(&[VK_CONTROL.0, VK_A.0]);
+        input.chord(&[VK_CONTROL.0, VK_V.0]);
+        input.key(VK_RETURN);
+        thread::sleep(PICKER_DELAY);
+    }
+
+    /// Waits out the break before `chunk`, counting down in the panel.
+    /// `false` when the draw was stopped meanwhile.
+    fn chunk_pause(&self, chunk: usize) -> bool {
+        for left in (1..=self.chunk_pause_seconds).rev() {
+            if !wait_while_paused() {
+                break;
+            }
+            self.chunk_countdown.store(Some((chunk, left)));
+            thread::sleep(Duration::from_secs(1));
+        }
+        self.chunk_countdown.store(None);
+        wait_while_paused()
+    }
+
+    /// Line drawings trace the simplified contours when simplification is on;
+    /// the other modes need every point.
+    fn draw_source<'a>(
+        &self,
+        lines: &'a [Contour<i32>],
+        simplified: &'a [Contour<i32>],
+    ) -> &'a [Contour<i32>] {
+        if self.draw_mode == DrawMode::Line
+            && self.simplification_epsilon > 0.0
+            && simplified.len() == lines.len()
+        {
+            simplified
+        } else {
+            lines
+        }
+    }
+
+    fn in_point_range(&self, contour: &Contour<i32>) -> bool {
+        (self.min_points..=self.max_points).contains(&contour.points.len())
+    }
+
+    /// Whether a contour passes the length, radius and area filters.
+    /// Generated patterns pass as they are.
+    fn keeps_shape(&self, contour: &Contour<i32>) -> bool {
+        self.draw_mode.is_generated()
+            || (self.in_point_range(contour)
+                && (self.min_bounding_radius_px <= 0.0
+                    || !fits_within(&contour.points, self.min_bounding_radius_px as f64))
+                && (self.min_area <= 0.0 || contour_area(&contour.points) >= self.min_area))
+    }
+
+    /// The contours a draw would visit, after the background and length filters.
+    fn drawable<'a>(&self, contours: &'a [Contour<i32>]) -> Vec<(usize, &'a Contour<i32>)> {
+        let background = self.background.read();
+        let excluded = self.excluded.read();
+        contours
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                !(self.skip_background && background.get(*index).copied().unwrap_or(false))
+            })
+            .filter(|(index, _)| !excluded.contains(index))
+            .filter(|(_, contour)| self.keeps_shape(contour))
+            .collect()
+    }
+
+    /// Runs the contours through the wave distortion, the test line scale
+    /// correction and the perspective calibration, whichever are set.
+    fn apply_warps(&self, contours: &[(usize, &Contour<i32>)]) -> Option<Vec<Contour<i32>>> {
+        let scale = self.px_per_screen_unit.load();
+        let rescale = (scale - 1.0).abs() > f64::EPSILON && scale > 0.0;
+        if self.wave.is_none() && self.homography.is_none() && !rescale {
+            return None;
+        }
+        let origin_x = self.center.read().0;
+        let center = self.draw_center();
+        Some(
+            contours
+                .iter()
+                .map(|(_, contour)| {
+                    let waved = self.wave.map(|wave| wave.apply(contour, origin_x));
+                    let contour = waved.as_ref().unwrap_or(contour);
+                    // Undo the measured stretch so lines come out at their real length.
+                    let rescaled = rescale.then(|| {
+                        let points = contour
+                            .points
+                            .iter()
+                            .map(|p| {
+                                Point::new(
+                                    center.x + ((p.x - center.x) as f64 / scale).round() as i32,
+                                    center.y + ((p.y - center.y) as f64 / scale).round() as i32,
+                                )
+                            })
+                            .collect();
+                        Contour::new(points, contour.border_type, contour.parent)
+                    });
+                    let contour = rescaled.as_ref().unwrap_or(contour);
+                    match self.homography {
+                        Some(homography) => homography.warp(contour),
+                        None => contour.clone(),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn start_calibration(&mut self) {
+        self.calibrating = true;
+        self.warp_source.clear();
+        self.warp_dest.clear();
+    }
+
+    /// Solves the warp once all four pairs of points are in.
+    fn finish_calibration(&mut self) {
+        let (Ok(source), Ok(dest)) = (
+            <[Point<i32>; 4]>::try_from(self.warp_source.as_slice()),
+            <[Point<i32>; 4]>::try_from(self.warp_dest.as_slice()),
+        ) else {
+            return;
+        };
+        self.homography = Homography::from_points(source, dest);
+        self.calibrating = false;
+        self.toast = Some((
+            if self.homography.is_some() {
+                t!("warp_applied")
+            } else {
+                t!("warp_failed")
+            }
+            .to_string(),
+            Instant::now(),
+        ));
+    }
+
+    /// Full-screen click catcher for picking the four on-screen corners.
+    fn show_calibration(&mut self, ctx: &egui::Context) {
+        let builder = egui::ViewportBuilder::default()
+            .with_title("AutoDraw Calibration")
+            .with_position([0.0, 0.0])
+            .with_inner_size([
+                SCREEN.0 as f32 / ctx.pixels_per_point(),
+                SCREEN.1 as f32 / ctx.pixels_per_point(),
+            ])
+            .with_decorations(false)
+            .with_transparent(true)
+            .with_always_on_top()
+            .with_taskbar(false);
+        let mut clicked = None;
+        let closed = ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("calibration"),
+            builder,
+            |ctx, _| {
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::none().fill(egui::Color32::from_black_alpha(40)))
+                    .show(ctx, |ui| {
+                        ui.colored_label(
+                            CANVAS_COLOR,
+                            t!("warp_dest", value = self.warp_dest.len() + 1),
+                        );
+                        let scale = 1.0 / ctx.pixels_per_point();
+                        for point in &self.warp_dest {
+                            ui.painter().circle_filled(
+                                egui::pos2(point.x as f32 * scale, point.y as f32 * scale),
+                                4.0,
+                                CANVAS_COLOR,
+                            );
+                        }
+                    });
+                if ctx.input(|i| i.pointer.primary_clicked()) {
+                    clicked = cursor_pos();
+                }
+                ctx.input(|i| i.viewport().close_requested() || i.key_pressed(egui::Key::Escape))
+            },
+        );
+        if let Some(point) = clicked {
+            self.warp_dest.push(point);
+            self.finish_calibration();
+        }
+        if closed {
+            self.calibrating = false;
+        }
+    }
+
+    /// Blocks until Enter (continue) or the stop key (abort) once the
+    /// thumbnail is drawn.
+    fn await_confirmation(&self) -> bool {
+        self.awaiting_confirmation.store(true);
+        let confirmed = loop {
+            if is_pressed(VK_RETURN.0) {
+                break true;
+            }
+            if is_pressed(self.hotkeys.stop) || matches!(STATE.load(), State::Stop) {
+                break false;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        self.awaiting_confirmation.store(false);
+        confirmed
+    }
+
+    fn benchmark(&self) {
+        let panel = self.clone();
+        rayon::spawn(move || {
+            STATE.store(State::Drawing);
+            DRAWING.store(true);
+            let center = if panel.resized_img.read().is_some() {
+                panel.draw_center()
+            } else {
+                Point::new(SCREEN.0 / 2, SCREEN.1 / 2)
+            };
+            let result = find_min_delay(center);
+            if result.is_some() {
+                panel.calibrated_min_delay_us.store(result);
+                let mut settings = load_settings();
+                settings.calibrated_min_delay_us = result;
+                settings.save().ok();
+            }
+            STATE.store(State::Stop);
+            DRAWING.store(false);
+        });
+    }
+
+    /// Draws a horizontal `TEST_LINE_PX` line through the draw center, to
+    /// be measured against the screen.
+    fn draw_test_line(&self) {
+        let panel = self.clone();
+        rayon::spawn(move || {
+            STATE.store(State::Drawing);
+            DRAWING.store(true);
+            let center = if panel.resized_img.read().is_some() {
+                panel.draw_center()
+            } else {
+                Point::new(SCREEN.0 / 2, SCREEN.1 / 2)
+            };
+            let mut input = Input::new(panel.backend, 0.0);
+            let start = center.x - TEST_LINE_PX / 2;
+            input.move_to(&Point::new(start, center.y), 0.0);
+            input.press(1.0);
+            for x in start..=start + TEST_LINE_PX {
+                if let State::Stop = STATE.load() {
+                    break;
+                }
+                input.move_to(&Point::new(x, center.y), 1.0);
+                thread::sleep(Duration::from_micros(panel.point_delay_us));
+            }
+            input.release();
+            STATE.store(State::Stop);
+            DRAWING.store(false);
+        });
+    }
+
+    /// Stores the correction from the measured test line.
+    fn apply_test_line(&self) {
+        let factor = self.measured_test_line_px / TEST_LINE_PX as f64;
+        self.px_per_screen_unit.store(factor);
+        let mut settings = load_settings();
+        settings.px_per_screen_unit = Some(factor);
+        settings.save().ok();
+    }
+
+    fn show_overlay(&mut self, ctx: &egui::Context) {
+        let builder = egui::ViewportBuilder::default()
+            .with_title("AutoDraw Overlay")
+            .with_position([0.0, 0.0])
+            .with_inner_size([
+                SCREEN.0 as f32 / ctx.pixels_per_point(),
+                SCREEN.1 as f32 / ctx.pixels_per_point(),
+            ])
+            .with_decorations(false)
+            .with_transparent(true)
+            .with_always_on_top()
+            .with_mouse_passthrough(true)
+            .with_taskbar(false);
+        let closed = ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("overlay"),
+            builder,
+            |ctx, _| {
+                egui::CentralPanel::default()
+                    .frame(egui::Frame::none())
+                    .show(ctx, |ui| {
+                        let scale = 1.0 / ctx.pixels_per_point();
+                        if let Some(rect) = self.canvas {
+                            let min = egui::pos2(rect.x as f32 * scale, rect.y as f32 * scale);
+                            let size = egui::vec2(rect.width as f32, rect.height as f32) * scale;
+                            ui.painter().rect_stroke(
+                                egui::Rect::from_min_size(min, size),
+                                0.0,
+                                egui::Stroke::new(2.0, CANVAS_COLOR),
+                            );
+                        }
+                        let lines = self.lines.read();
+                        let background = self.background.read();
+                        for (index, contour) in lines.iter().flatten().enumerate() {
+                            let color = if background.get(index).copied().unwrap_or(false) {
+                                OVERLAY_COLOR.gamma_multiply(0.3)
+                            } else {
+                                OVERLAY_COLOR
+                            };
+                            let points = contour
+                                .points
+                                .iter()
+                                .map(|p| egui::pos2(p.x as f32 * scale, p.y as f32 * scale))
+                                .collect();
+                            ui.painter()
+                                .add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+                        }
+                    });
+                ctx.input(|i| i.viewport().close_requested())
+            },
+        );
+        if closed {
+            self.show_overlay = false;
+        }
+    }
+
+    /// Paints contour `index` over the preview image shown in `rect`.
+    fn paint_contour(&self, ui: &egui::Ui, rect: egui::Rect, index: usize, color: egui::Color32) {
+        let lines = self.lines.read();
+        let Some(contour) = lines.as_ref().and_then(|lines| lines.get(index)) else {
+            return;
+        };
+        self.paint_points(ui, rect, &contour.points, egui::Stroke::new(2.0, color));
+    }
+
+    /// Paints the simplified contours over the preview, `opacity` of the way
+    /// faded in.
+    fn paint_simplified(&self, ui: &egui::Ui, rect: egui::Rect, opacity: f32) {
+        let color = ui.visuals().strong_text_color().gamma_multiply(opacity);
+        for contour in self.simplified.read().iter() {
+            self.paint_points(ui, rect, &contour.points, egui::Stroke::new(1.0, color));
+        }
+    }
+
+    /// Percentage of inked pixels the drawable contours and hatches go over
+    /// more than once.
+    fn compute_stroke_overlap(&self) -> Option<f32> {
+        let (width, height) = self.resized_img.read().as_ref()?.dimensions();
+        let lines = self.lines.read();
+        let simplified = self.simplified.read();
+        let hatches = self.hatches.read();
+        let drawable = self.drawable(self.draw_source(lines.as_ref()?, &simplified));
+        let local = image_space(
+            drawable
+                .into_iter()
+                .map(|(_, contour)| contour)
+                .chain(hatches.iter()),
+            *self.center.read(),
+        );
+        Some(stroke_overlap(&local, width, height) * 100.0)
+    }
+
+    /// Per-cell point counts over a `coverage_grid`-square grid on the image,
+    /// normalized to the busiest cell.
+    fn compute_contour_density_map(&self) -> Option<Vec<f32>> {
+        let (width, height) = self.resized_img.read().as_ref()?.dimensions();
+        let center = *self.center.read();
+        let lines = self.lines.read();
+        // The stored contours are in screen space; the grid is over the image.
+        let local = image_space(lines.as_ref()?, center);
+        Some(grid_density(&local, width, height, self.coverage_grid))
+    }
+
+    fn paint_coverage(&self, ui: &egui::Ui, rect: egui::Rect, cells: &[f32]) {
+        let grid = self.coverage_grid.max(1) as usize;
+        let size = egui::vec2(rect.width() / grid as f32, rect.height() / grid as f32);
+        for (index, value) in cells.iter().enumerate() {
+            let min = rect.min
+                + egui::vec2(
+                    (index % grid) as f32 * size.x,
+                    (index / grid) as f32 * size.y,
+                );
+            let color =
+                egui::Color32::from_rgb((value * 255.0) as u8, 0, ((1.0 - value) * 255.0) as u8)
+                    .gamma_multiply(0.5);
+            ui.painter()
+                .rect_filled(egui::Rect::from_min_size(min, size), 0.0, color);
+        }
+    }
+
+    /// Mini-map of the canvas with the bounds of every finished stroke shaded.
+    fn paint_drawing_map(&self, ui: &mut egui::Ui, stats: &[StrokeStat]) {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(80.0, 80.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+        let extent = self.draw_extent();
+        if extent.width == 0 || extent.height == 0 {
+            return;
+        }
+        let scale = rect.width() / extent.width.max(extent.height) as f32;
+        let lines = self.lines.read();
+        let hatches = self.hatches.read();
+        let lines = lines.as_deref().unwrap_or_default();
+        for stat in stats {
+            let contour = lines
+                .get(stat.index)
+                .or_else(|| hatches.get(stat.index - lines.len()));
+            let Some(contour) = contour else {
+                continue;
+            };
+            let (min, max) = contour.points.iter().fold(
+                (
+                    Point::new(i32::MAX, i32::MAX),
+                    Point::new(i32::MIN, i32::MIN),
+                ),
+                |(min, max), p| {
+                    (
+                        Point::new(min.x.min(p.x), min.y.min(p.y)),
+                        Point::new(max.x.max(p.x), max.y.max(p.y)),
+                    )
+                },
+            );
+            let to_map = |p: Point<i32>| {
+                rect.min
+                    + egui::vec2(
+                        (p.x - extent.x) as f32 * scale,
+                        (p.y - extent.y) as f32 * scale,
+                    )
+            };
+            painter.rect_filled(
+                egui::Rect::from_min_max(to_map(min), to_map(max)).expand(0.5),
+                0.0,
+                DRAWN_COLOR,
+            );
+        }
+    }
+
+    /// Pinch over the preview to zoom around the pointer, drag to pan,
+    /// double-click to fit it again. The wheel is left to
+    /// [`Self::zoom_preview`], which takes it first.
+    fn zoom_and_pan(&mut self, ui: &egui::Ui, response: &egui::Response) {
+        let view = response.rect;
+        if response.double_clicked() {
+            self.zoom_scale = 1.0;
+            self.pan_offset = egui::Vec2::ZERO;
+            return;
+        }
+        if response.hovered() {
+            let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let factor = pinch * (scroll / 200.0).exp();
+            if factor != 1.0 {
+                let zoom = (self.zoom_scale * factor).clamp(1.0, MAX_ZOOM);
+                let anchor = response.hover_pos().unwrap_or(view.center()) - view.min;
+                self.pan_offset = anchor - (anchor - self.pan_offset) * (zoom / self.zoom_scale);
+                self.zoom_scale = zoom;
+            }
+        }
+        if response.dragged_by(egui::PointerButton::Primary) && !self.calibrating {
+            self.pan_offset += response.drag_delta();
+        }
+        // Keep the image covering the whole view.
+        let slack = view.size() * (self.zoom_scale - 1.0);
+        self.pan_offset = self.pan_offset.clamp(-slack, egui::Vec2::ZERO);
+    }
+
+    /// Wheel over the preview to scale it around the pointer, middle-drag to
+    /// scroll it. Gives back the scroll position to move to, if it changed.
+    fn zoom_preview(&mut self, ui: &egui::Ui, response: &egui::Response) -> Option<egui::Vec2> {
+        let mut offset = None;
+        if response.hovered() {
+            let scroll = ui.input_mut(|i| std::mem::take(&mut i.smooth_scroll_delta).y);
+            let zoom = (self.preview_zoom * (scroll / 200.0).exp())
+                .clamp(PREVIEW_MIN_ZOOM, PREVIEW_MAX_ZOOM);
+            if zoom != self.preview_zoom {
+                let anchor = response.hover_pos().unwrap_or(response.rect.min) - response.rect.min;
+                offset = Some(self.preview_offset + anchor * (zoom / self.preview_zoom - 1.0));
+                self.preview_zoom = zoom;
+            }
+        }
+        if response.dragged_by(egui::PointerButton::Middle) {
+            offset = Some(offset.unwrap_or(self.preview_offset) - response.drag_delta());
+        }
+        offset
+    }
+
+    fn paint_markers(&self, ui: &egui::Ui, rect: egui::Rect, points: &[Point<i32>]) {
+        for point in points {
+            if let Some(pos) = self.preview_pos(rect, point) {
+                ui.painter().circle_filled(pos, 4.0, CANVAS_COLOR);
+            }
+        }
+    }
+
+    /// Maps a screen point onto the preview image shown in `rect`.
+    fn preview_pos(&self, rect: egui::Rect, point: &Point<i32>) -> Option<egui::Pos2> {
+        let width = self.resized_img.read().as_ref()?.width();
+        let center = *self.center.read();
+        let scale = rect.width() / width as f32;
+        Some(
+            rect.min
+                + egui::vec2(
+                    (point.x - center.0) as f32 * scale,
+                    (point.y - center.1) as f32 * scale,
+                ),
+        )
+    }
+
+    /// Maps a position on the preview image shown in `rect` back to the screen.
+    fn screen_point(&self, rect: egui::Rect, pos: egui::Pos2) -> Option<Point<i32>> {
+        let width = self.resized_img.read().as_ref()?.width();
+        let center = *self.center.read();
+        let offset = (pos - rect.min) * (width as f32 / rect.width());
+        Some(Point::new(
+            center.0 + offset.x.round() as i32,
+            center.1 + offset.y.round() as i32,
+        ))
+    }
+
+    fn paint_points(
+        &self,
+        ui: &egui::Ui,
+        rect: egui::Rect,
+        points: &[Point<i32>],
+        stroke: egui::Stroke,
+    ) {
+        let points = points
+            .iter()
+            .filter_map(|p| self.preview_pos(rect, p))
+            .collect();
+        ui.painter()
+            .with_clip_rect(rect)
+            .add(egui::Shape::line(points, stroke));
+    }
+
+    /// The detected canvas, or the area the image will be drawn in.
+    fn draw_extent(&self) -> ScreenRect {
+        if let Some(canvas) = self.canvas {
+            return canvas;
+        }
+        let center = *self.center.read();
+        let (width, height) = self
+            .resized_img
+            .read()
+            .as_ref()
+            .map(|image| image.dimensions())
+            .unwrap_or_default();
+        ScreenRect {
+            x: center.0,
+            y: center.1,
+            width,
+            height,
+        }
+    }
+
+    fn draw_center(&self) -> Point<i32> {
+        let center = *self.center.read();
+        let (width, height) = self
+            .resized_img
+            .read()
+            .as_ref()
+            .map(|image| image.dimensions())
+            .unwrap_or_default();
+        Point::new(center.0 + width as i32 / 2, center.1 + height as i32 / 2)
+    }
+
+    fn draw_contours(&self, input: &mut Input, contours: &[(usize, &Contour<i32>)]) {
+        let detail = self.detail.read();
+        let depth = self.depth.read();
+        let center = *self.center.read();
+        let mut trail = Trail::new(self.trail_skip_radius_px);
+        let mut travelled = false;
+        let palette = self.palette.read();
+        let mut brush_color = None;
+
+        for &(contour_index, contour) in contours {
+            if let State::Stop = STATE.load() {
+                input.release();
+                break;
+            }
+            if SKIP.swap(false) {
+                self.drawn_points.fetch_add(contour.points.len());
+                continue;
+            }
+            trail.next_stroke();
+            let snapped = self.snap_first_point(&contour.points);
+            let points = snapped.as_deref().unwrap_or(&contour.points);
+            if let Some(first) = points.first().filter(|_| travelled) {
+                thread::sleep(self.pen_up_delay(input.position(), *first));
+                for point in travel_path(self.pen_up_style, input.position(), *first) {
+                    input.move_to(&point, 0.0);
+                    thread::sleep(Duration::from_micros(self.point_delay_us));
+                }
+            }
+            travelled = true;
+            if let Some(color) = palette
+                .as_ref()
+                .and_then(|palette| palette.color_of(contour_index))
+                .filter(|color| Some(*color) != brush_color)
+            {
+                brush_color = Some(color);
+                self.select_color(input, color);
+            }
+            for key in &self.pre_stroke_keys {
+                input.key(VIRTUAL_KEY(*key));
+            }
+            let started = Instant::now();
+            let hold = self
+                .long_press_threshold_ms
+                .filter(|_| (1..=LONG_PRESS_MAX_POINTS).contains(&points.len()));
+            if let Some(hold) = hold {
+                let pressure = stroke_pressure(points, 0);
+                input.move_to(&points[0], pressure);
+                input.press(pressure);
+                thread::sleep(Duration::from_millis(hold));
+                self.drawn_points.fetch_add(points.len());
+                input.release();
+            } else {
+                match self.draw_mode {
+                    DrawMode::Line
+                    | DrawMode::Spiral
+                    | DrawMode::HilbertScan
+                    | DrawMode::Voronoi
+                    | DrawMode::Contourf
+                    | DrawMode::Peano
+                    | DrawMode::ConcentricCircles
+                    | DrawMode::ErrorDiffusionHalftone
+                    | DrawMode::StringArt
+                    | DrawMode::Truchet
+                    | DrawMode::KochSnowflake
+                    | DrawMode::Penrose => {
+                        let mut down = false;
+                        for (index, point) in points.iter().enumerate() {
+                            self.drawn_points.fetch_add(1);
+                            // Lift the pen for the pause; it goes back down on
+                            // this point once drawing resumes.
+                            if down && STATE.load() == State::Paused {
+                                input.release();
+                                down = false;
+                            }
+                            // A skip mid-stroke drops the rest of this contour.
+                            if !wait_while_paused() || SKIP.swap(false) {
+                                break;
+                            }
+                            // Lift over spots that were just inked instead of
+                            // dragging through them again.
+                            if trail.revisits(point, index) {
+                                if down {
+                                    input.release();
+                                    down = false;
+                                }
+                                continue;
+                            }
+                            let pressure = stroke_pressure(points, index);
+                            input.move_to(point, pressure);
+                            if !down {
+                                input.press(pressure);
+                                down = true;
+                            }
+                            trail.visit(point, index);
+                            thread::sleep(self.point_delay(detail.as_ref(), center, points, index));
+                        }
+                        input.release();
+                    }
+                    DrawMode::Stipple { min_spacing_px }
+                    | DrawMode::Stipple3D { min_spacing_px } => {
+                        let min_spacing = min_spacing_px as f32;
+                        let mut last: Option<&Point<i32>> = None;
+                        for (index, point) in points.iter().enumerate() {
+                            self.drawn_points.fetch_add(1);
+                            // A skip mid-stroke drops the rest of this contour.
+                            if !wait_while_paused() || SKIP.swap(false) {
+                                break;
+                            }
+                            if last.is_some_and(|last| distance(last, point) < min_spacing)
+                                || trail.revisits(point, index)
+                            {
+                                continue;
+                            }
+                            last = Some(point);
+                            trail.visit(point, index);
+                            let pressure = stroke_pressure(points, index);
+                            input.move_to(point, pressure);
+                            match depth.as_ref() {
+                                // Holding the button longer leaves a bigger dot in
+                                // brush-based apps, so nearer points read heavier.
+                                Some(depth) => {
+                                    let near =
+                                        depth_at(depth, point.x - center.0, point.y - center.1);
+                                    input.press(pressure);
+                                    thread::sleep(
+                                        Duration::from_millis(self.max_dwell_ms).mul_f32(near),
+                                    );
+                                    input.release();
+                                }
+                                None => {
+                                    input.click(pressure);
+                                }
+                            }
+                            thread::sleep(self.point_delay(detail.as_ref(), center, points, index));
+                        }
+                    }
+                }
+            }
+            self.stroke_stats.write().push(StrokeStat {
+                index: contour_index,
+                points: points.len(),
+                duration: started.elapsed(),
+            });
+            if let Some(key) = self.new_stroke_key {
+                input.key(key);
+            }
+            thread::sleep(Duration::from_millis(self.contour_delay_ms));
+        }
+        self.input_errors.fetch_add(input.take_failed());
+    }
+
+    /// With grid snapping on, a copy of `points` whose first point sits on
+    /// the nearest grid vertex, so the pen goes down there.
+    fn snap_first_point(&self, points: &[Point<i32>]) -> Option<Vec<Point<i32>>> {
+        if !self.grid_snap_first_point {
+            return None;
+        }
+        let first = points.first()?;
+        let snap = |value: i32, origin: i32, cell: u32| {
+            let cell = cell.max(1) as f32;
+            origin + (((value - origin) as f32 / cell).round() * cell) as i32
+        };
+        let mut points = points.to_vec();
+        points[0] = Point::new(
+            snap(first.x, self.grid_origin.0, self.grid_cell.0),
+            snap(first.y, self.grid_origin.1, self.grid_cell.1),
+        );
+        Some(points)
+    }
+
+    /// Time the lifted pen takes to cover the distance between two points.
+    fn pen_up_delay(&self, from: Point<i32>, to: Point<i32>) -> Duration {
+        let distance = ((to.x - from.x) as f32).hypot((to.y - from.y) as f32);
+        Duration::from_secs_f32(distance / self.pen_travel_speed_px_per_us.max(f32::EPSILON) / 1e6)
+    }
+
+    fn point_delay(
+        &self,
+        detail: Option<&GrayImage>,
+        center: (i32, i32),
+        points: &[Point<i32>],
+        index: usize,
+    ) -> Duration {
+        let point = &points[index];
+        let mut factor = match detail {
+            Some(map) if self.speed_factor > 0.0 => {
+                1.0 + detail_at(map, point.x - center.0, point.y - center.1) * self.speed_factor
+            }
+            _ => 1.0,
+        };
+        // Dwelling on a corner lets apps that build up ink over time weight it.
+        if turn_angle(points, index).to_degrees() > self.corner_threshold_deg {
+            factor *= self.corner_dwell_factor;
+        }
+        let progress = index as f64 / (points.len() - 1).max(1) as f64;
+        factor *= self.speed_profile.multiplier(progress) as f32;
+        Duration::from_micros(self.point_delay_us).mul_f32(factor)
+    }
+}
+
+impl App for Panel {
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        // The overlay viewport must stay see-through; the main window is
+        // fully covered by its central panel anyway.
+        [0.0; 4]
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let mut settings = load_settings();
+        settings.window_pos = self.window_pos.map(|pos| (pos.x, pos.y));
+        if let Some(size) = self.window_size {
+            settings.window_size = (size.x, size.y);
+        }
+        settings.open_sections = self.open_sections.clone();
+        settings.save().ok();
+    }
+
+    fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
+        ctx.request_repaint();
+        ctx.input(|input| {
+            let viewport = input.viewport();
+            if viewport.minimized != Some(true) && viewport.maximized != Some(true) {
+                self.window_pos = viewport.outer_rect.map(|rect| rect.min).or(self.window_pos);
+                self.window_size = viewport
+                    .inner_rect
+                    .map(|rect| rect.size())
+                    .or(self.window_size);
+            }
+        });
+        self.autosave_config(ctx);
+        let imported = self.imported_session.write().take();
+        if let Some(session) = imported {
+            self.apply_session(ctx, session);
+        }
+        let mut comparing = self.comparison.read().is_some();
+        egui::Window::new(t!("compare_to_reference"))
+            .open(&mut comparing)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.compare_alpha, 0.0..=1.0).text(t!("compare_alpha")),
+                );
+                if let Some(Comparison {
+                    reference,
+                    drawn,
+                    size: [width, height],
+                }) = self.comparison.read().as_ref()
+                {
+                    let scale = (COMPARE_SIZE / *width.max(height) as f32).min(1.0);
+                    let size = egui::vec2(*width as f32, *height as f32) * scale;
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            Image::from_bytes(drawn.id.to_string(), drawn.buf.to_vec())
+                                .fit_to_exact_size(size),
+                        );
+                        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+                        Image::from_bytes(drawn.id.to_string(), drawn.buf.to_vec())
+                            .paint_at(ui, rect);
+                        Image::from_bytes(reference.id.to_string(), reference.buf.to_vec())
+                            .tint(egui::Color32::from_white_alpha(
+                                (self.compare_alpha * 255.0) as u8,
+                            ))
+                            .paint_at(ui, rect);
+                    });
+                }
+            });
+        if !comparing {
+            self.comparison.write().take();
+        }
+        egui::SidePanel::right("hierarchy")
+            .resizable(true)
+            .default_width(260.0)
+            .show_animated(ctx, self.show_hierarchy, |ui| {
+                ui.heading(t!("hierarchy"));
+                if let Some(lines) = self.lines.read().as_ref() {
+                    hierarchy_tree(ui, lines, &mut self.highlight, &mut self.excluded.write());
+                }
+            });
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                if ui.button(t!("open_image")).clicked() {
+                    ctx.forget_all_images();
+                    self.pre_crop = None;
+                    self.open_image(false);
+                }
+                if ui
+                    .button(t!("capture_region"))
+                    .on_hover_text(t!("capture_region_hint"))
+                    .clicked()
+                {
+                    self.capture_region(ctx);
+                }
+                if ui.button(t!("load_mask")).clicked() {
+                    ctx.forget_all_images();
+                    self.pre_crop = None;
+                    self.open_image(true);
+                }
+                if ui.button(t!("export_svg")).clicked() {
+                    self.export_svg();
+                }
+                if ui.button(t!("export_session")).clicked() {
+                    self.export_session();
+                }
+                if ui.button(t!("import_session")).clicked() {
+                    self.import_session();
+                }
+                if ui.button(t!("generate_report")).clicked() {
+                    self.save_report();
+                }
+                if ui
+                    .add_enabled(
+                        self.last_draw_duration.load().is_some() && !DRAWING.load(),
+                        egui::Button::new(t!("compare_to_reference")),
+                    )
+                    .on_hover_text(t!("compare_to_reference_hint"))
+                    .clicked()
+                {
+                    self.compare_to_reference(ctx);
+                }
+                if ui
+                    .add_enabled(
+                        !self.stroke_stats.read().is_empty() && !self.stats_from_passes.load(),
+                        egui::Button::new(t!("export_timelapse")),
+                    )
+                    .on_hover_text(t!("export_timelapse_hint"))
+                    .clicked()
+                {
+                    self.export_timelapse_png_sequence();
+                }
+                let mut open_svg = self.auto_open_svg_in.is_some();
+                if ui.checkbox(&mut open_svg, t!("open_svg_in")).changed() {
+                    self.auto_open_svg_in = open_svg.then(|| "inkscape".to_string());
+                }
+                if let Some(program) = &mut self.auto_open_svg_in {
+                    ui.add(egui::TextEdit::singleline(program).desired_width(120.0))
+                        .on_hover_text(t!("open_svg_in_hint"));
+                }
+                let idle = !DRAWING.load();
+                let undo = self.history.next_undo().map(Param::describe);
+                let response = ui.add_enabled(idle && undo.is_some(), egui::Button::new("⟲"));
+                if response
+                    .on_hover_text(t!("undo", value = undo.unwrap_or_default()))
+                    .clicked()
+                {
+                    self.undo(ctx);
+                }
+                let redo = self.history.next_redo().map(Param::describe);
+                let response = ui.add_enabled(idle && redo.is_some(), egui::Button::new("⟳"));
+                if response
+                    .on_hover_text(t!("redo", value = redo.unwrap_or_default()))
+                    .clicked()
+                {
+                    self.redo(ctx);
+                }
+                if ui
+                    .add_enabled(idle, egui::Button::new(t!("reset_all")))
+                    .clicked()
+                {
+                    self.reset_to_defaults(ctx);
+                }
+                if ui.button(t!("detect_area")).clicked() {
+                    self.detect_area(ctx);
+                }
+                if ui
+                    .add_enabled(idle, egui::Button::new(t!("sample_canvas")))
+                    .on_hover_text(t!("sample_canvas_hint"))
+                    .clicked()
+                {
+                    self.auto_scale_to_window(ctx);
+                }
+                let centering = self.centering.load();
+                if ui
+                    .add_enabled(!centering, egui::Button::new(t!("center_here")))
+                    .on_hover_text(t!("center_here_hint"))
+                    .clicked()
+                {
+                    self.center_on_click();
+                }
+                if centering {
+                    ui.colored_label(HIGHLIGHT_COLOR, t!("center_here_waiting"));
+                }
+                if let Some(point) = self.picked_center.take() {
+                    self.offset_x = point.x - SCREEN.0 / 2;
+                    self.offset_y = point.y - SCREEN.1 / 2;
+                    ctx.forget_all_images();
+                    self.reload(true);
+                }
+                let before = (self.offset_x, self.offset_y);
+                let x = ui.add(
+                    egui::DragValue::new(&mut self.offset_x)
+                        .range(-2000..=2000)
+                        .prefix(t!("offset_x")),
+                );
+                let y = ui.add(
+                    egui::DragValue::new(&mut self.offset_y)
+                        .range(-2000..=2000)
+                        .prefix(t!("offset_y")),
+                );
+                if x.changed() || y.changed() {
+                    // The image keeps its size, so shifting the center is enough.
+                    {
+                        let mut center = self.center.write();
+                        center.0 += self.offset_x - before.0;
+                        center.1 += self.offset_y - before.1;
+                    }
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                if ui.button(t!("reset_to_center")).clicked() {
+                    self.offset_x = 0;
+                    self.offset_y = 0;
+                    ctx.forget_all_images();
+                    self.reload(true);
+                }
+                if ui
+                    .button(t!("smart_crop"))
+                    .on_hover_text(t!("smart_crop_hint"))
+                    .clicked()
+                {
+                    self.smart_crop(ctx);
+                }
+                if self.pre_crop.is_some() && ui.button(t!("clear_crop")).clicked() {
+                    self.pre_crop = None;
+                    ctx.forget_all_images();
+                    self.reload(true);
+                }
+                if ui
+                    .add_enabled(!DRAWING.load(), egui::Button::new(t!("benchmark")))
+                    .on_hover_text(t!("benchmark_hint", key = key_name(self.hotkeys.stop)))
+                    .clicked()
+                {
+                    self.benchmark();
+                }
+                if let Some(delay) = self.calibrated_min_delay_us.load() {
+                    ui.label(t!("min_delay", value = delay));
+                }
+                if ui
+                    .add_enabled(!DRAWING.load(), egui::Button::new(t!("test_line")))
+                    .on_hover_text(t!("test_line_hint"))
+                    .clicked()
+                {
+                    self.draw_test_line();
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.measured_test_line_px)
+                        .range(1.0..=1000.0)
+                        .suffix("px")
+                        .prefix(t!("measured_line")),
+                );
+                if ui.button(t!("apply_measurement")).clicked() {
+                    self.apply_test_line();
+                }
+                ui.label(t!(
+                    "px_per_screen_unit",
+                    value = format!("{:.3}", self.px_per_screen_unit.load())
+                ));
+                if ui
+                    .selectable_value(&mut self.language, Language::Chinese, "简体中文")
+                    .clicked()
+                {
+                    set_locale(self.language);
+                }
+                if ui
+                    .selectable_value(&mut self.language, Language::English, "English")
+                    .clicked()
+                {
+                    set_locale(self.language);
+                }
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                for method in EdgeMethod::ALL {
+                    if ui
+                        .radio_value(&mut self.edge_method, method, edge_method_label(method))
+                        .changed()
+                    {
+                        ctx.forget_all_images();
+                        self.reload(false);
+                    }
+                }
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.sweep_start)
+                        .range(1..=self.sweep_end)
+                        .prefix(t!("sweep_start")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.sweep_end)
+                        .range(self.sweep_start.min(self.canny_high_value)..=self.canny_high_value)
+                        .prefix(t!("sweep_end")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.sweep_step)
+                        .range(1..=u32::MAX)
+                        .prefix(t!("sweep_step")),
+                );
+                if ui
+                    .add_enabled(
+                        self.lines.read().is_some() && !self.sweeping.load() && !DRAWING.load(),
+                        egui::Button::new(t!("batch_calibration")),
+                    )
+                    .on_hover_text(t!("batch_calibration_hint"))
+                    .clicked()
+                {
+                    self.batch_calibration(ctx);
+                }
+            });
+            ui.horizontal_wrapped(|ui| {
+                let before = self.canny_value;
+                let response = ui.add(
+                    egui::DragValue::new(&mut self.canny_value)
+                        .range(1..=self.canny_high_value)
+                        .prefix(t!("low_threshold")),
+                );
+                self.record_change(&response, Param::Canny(before));
+                if response.changed() {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                let before = self.canny_high_value;
+                let response = ui.add(
+                    egui::DragValue::new(&mut self.canny_high_value)
+                        .range(self.canny_value..=u32::MAX)
+                        .prefix(t!("high_threshold")),
+                );
+                self.record_change(&response, Param::CannyHigh(before));
+                if response.changed() {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                let before = self.area;
+                let response = ui.add(
+                    egui::DragValue::new(&mut self.area)
+                        .range(0..=100)
+                        .prefix(t!("draw_area"))
+                        .custom_formatter(|n, _| format!("{n}%")),
+                );
+                self.record_change(&response, Param::Area(before));
+                if response.changed() {
+                    ctx.forget_all_images();
+                    self.reload(true);
+                }
+                let before = self.filter_type;
+                egui::ComboBox::from_label(t!("resize_filter"))
+                    .selected_text(resize_filter_label(self.filter_type))
+                    .show_ui(ui, |ui| {
+                        for filter in ResizeFilter::ALL {
+                            ui.selectable_value(
+                                &mut self.filter_type,
+                                filter,
+                                resize_filter_label(filter),
+                            );
+                        }
+                    });
+                if self.filter_type != before {
+                    ctx.forget_all_images();
+                    self.reload(true);
+                }
+                for mode in ResizeMode::ALL {
+                    if ui
+                        .radio_value(&mut self.resize_mode, mode, resize_mode_label(mode))
+                        .changed()
+                    {
+                        ctx.forget_all_images();
+                        self.reload(true);
+                    }
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.min_points)
+                        .range(0..=self.max_points)
+                        .prefix(t!("pass_points")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.max_points)
+                        .range(self.min_points..=usize::MAX)
+                        .prefix(t!("max_points")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.min_area)
+                        .range(0.0..=f64::MAX)
+                        .suffix("px²")
+                        .prefix(t!("min_area")),
+                )
+                .on_hover_text(t!("min_area_hint"));
+                ui.add(
+                    egui::DragValue::new(&mut self.min_bounding_radius_px)
+                        .range(0.0..=500.0)
+                        .suffix("px")
+                        .prefix(t!("min_bounding_radius")),
+                )
+                .on_hover_text(t!("min_bounding_radius_hint"));
+                if ui.checkbox(&mut self.is_binary, t!("is_binary")).changed() {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                if ui
+                    .checkbox(&mut self.invert_colors, t!("invert_colors"))
+                    .changed()
+                {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut self.median_radius)
+                            .range(0..=5)
+                            .prefix(t!("median_radius")),
+                    )
+                    .changed()
+                {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut self.adaptive_blur_sigma)
+                            .range(0.0..=10.0)
+                            .speed(0.05)
+                            .prefix(t!("adaptive_blur")),
+                    )
+                    .on_hover_text(t!("adaptive_blur_hint"))
+                    .changed()
+                {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                if ui
+                    .add(
+                        egui::DragValue::new(&mut self.blur_sigma)
+                            .range(0.0..=10.0)
+                            .speed(0.05)
+                            .fixed_decimals(2)
+                            .prefix(t!("blur_sigma")),
+                    )
+                    .on_hover_text(t!("blur_sigma_hint"))
+                    .changed()
+                {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                let brightness = ui.add(
+                    egui::DragValue::new(&mut self.brightness)
+                        .range(-128..=128)
+                        .prefix(t!("brightness")),
+                );
+                let contrast = ui.add(
+                    egui::DragValue::new(&mut self.contrast)
+                        .range(-1.0..=3.0)
+                        .speed(0.01)
+                        .fixed_decimals(2)
+                        .prefix(t!("contrast")),
+                );
+                if brightness.changed() || contrast.changed() {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                let dilate = ui.add(
+                    egui::DragValue::new(&mut self.dilate_iters)
+                        .range(0..=5)
+                        .prefix(t!("dilate_iters")),
+                );
+                let erode = ui.add(
+                    egui::DragValue::new(&mut self.erode_iters)
+                        .range(0..=5)
+                        .prefix(t!("erode_iters")),
+                );
+                if dilate.changed() || erode.changed() {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                let sharpen = ui.add(
+                    egui::DragValue::new(&mut self.unsharp_mask_sigma)
+                        .range(0.0..=5.0)
+                        .speed(0.05)
+                        .prefix(t!("unsharp_sigma")),
+                );
+                let amount = ui.add(
+                    egui::DragValue::new(&mut self.unsharp_amount)
+                        .range(0.0..=3.0)
+                        .speed(0.05)
+                        .prefix(t!("unsharp_amount")),
+                );
+                if sharpen.changed() || (amount.changed() && self.unsharp_mask_sigma > 0.0) {
+                    ctx.forget_all_images();
+                    self.reload(false);
+                }
+                ui.checkbox(&mut self.skip_background, t!("skip_background"));
+                ui.checkbox(&mut self.thumbnail_first, t!("thumbnail_first"));
+                ui.checkbox(&mut self.hide_during_draw, t!("hide_during_draw"));
+                if ui
+                    .checkbox(&mut self.always_on_top, t!("always_on_top"))
+                    .changed()
+                {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(window_level(
+                        self.always_on_top,
+                    )));
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.chunks)
+                        .range(1..=10)
+                        .prefix(t!("chunks")),
+                );
+                if self.chunks > 1 {
+                    ui.add(
+                        egui::DragValue::new(&mut self.chunk_pause_seconds)
+                            .range(0..=3600)
+                            .suffix("s")
+                            .prefix(t!("chunk_pause")),
+                    );
+                }
+                let mut limited = self.max_contours.is_some();
+                if ui.checkbox(&mut limited, t!("max_contours")).changed() {
+                    self.max_contours = limited.then_some(100);
+                }
+                if let Some(max) = &mut self.max_contours {
+                    ui.add(egui::DragValue::new(max).range(1..=usize::MAX));
+                }
+                ui.checkbox(&mut self.show_progress_border, t!("progress_border"));
+                if self.show_progress_border {
+                    ui.add(
+                        egui::DragValue::new(&mut self.border_width)
+                            .range(1..=5)
+                            .suffix("px")
+                            .prefix(t!("border_width")),
+                    );
+                }
+                ui.checkbox(&mut self.show_overlay, t!("show_overlay"));
+                ui.checkbox(&mut self.show_density, t!("show_density"));
+                ui.checkbox(&mut self.show_hierarchy, t!("show_hierarchy"));
+                ui.checkbox(&mut self.show_coverage, t!("show_coverage"));
+                if self.show_coverage {
+                    ui.add(
+                        egui::DragValue::new(&mut self.coverage_grid)
+                            .range(2..=64)
+                            .prefix(t!("coverage_grid")),
+                    );
+                }
+                let before = self.target_window.clone();
+                egui::ComboBox::from_label(t!("target_window"))
+                    .selected_text(
+                        self.target_window
+                            .clone()
+                            .unwrap_or_else(|| t!("none").to_string()),
+                    )
+                    .width(160.0)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.target_window, None, t!("none"));
+                        for title in window_titles() {
+                            ui.selectable_value(
+                                &mut self.target_window,
+                                Some(title.clone()),
+                                title,
+                            );
+                        }
+                    });
+                if self.target_window != before {
+                    let mut settings = load_settings();
+                    settings.target_window.clone_from(&self.target_window);
+                    settings.save().ok();
+                    self.detect_profile();
+                }
+                let mut chosen = None;
+                egui::ComboBox::from_label(t!("select_profile"))
+                    .selected_text(
+                        self.profile
+                            .clone()
+                            .unwrap_or_else(|| t!("none").to_string()),
+                    )
+                    .show_ui(ui, |ui| {
+                        for profile in builtin_profiles() {
+                            let selected = self.profile.as_ref() == Some(&profile.name);
+                            if ui.selectable_label(selected, &profile.name).clicked() {
+                                chosen = Some(profile);
+                            }
+                        }
+                    });
+                if let Some(profile) = chosen {
+                    self.apply_profile(&profile);
+                }
+                ui.selectable_value(&mut self.backend, Backend::Mouse, t!("backend_mouse"));
+                if ui
+                    .selectable_value(
+                        &mut self.backend,
+                        Backend::VirtualTablet,
+                        t!("backend_tablet"),
+                    )
+                    .changed()
+                {
+                    self.check_backend();
+                }
+                egui::ComboBox::from_label(t!("pen_up_style"))
+                    .selected_text(pen_up_label(self.pen_up_style))
+                    .show_ui(ui, |ui| {
+                        for style in PenUpStyle::ALL {
+                            ui.selectable_value(&mut self.pen_up_style, style, pen_up_label(style));
+                        }
+                    });
+                ui.checkbox(&mut self.optimize_order, t!("optimize_order"))
+                    .on_hover_text(t!("optimize_order_hint"));
+                ui.add_enabled_ui(!self.optimize_order, |ui| {
+                    egui::ComboBox::from_label(t!("draw_order"))
+                        .selected_text(draw_order_label(self.draw_order))
+                        .show_ui(ui, |ui| {
+                            for order in DrawOrder::ALL {
+                                ui.selectable_value(
+                                    &mut self.draw_order,
+                                    order,
+                                    draw_order_label(order),
+                                );
+                            }
+                        });
+                });
+                if self.draw_order == DrawOrder::Importance && !self.optimize_order {
+                    let score = &mut self.contour_score;
+                    for (weight, label) in [
+                        (&mut score.length, t!("importance_length")),
+                        (&mut score.gradient, t!("importance_gradient")),
+                        (&mut score.center, t!("importance_center")),
+                    ] {
+                        ui.add(egui::Slider::new(weight, 0.0..=1.0).text(label));
+                    }
                 }
-                if contour.points.len() <= point_count {
-                    continue;
+                if self.backend == Backend::VirtualTablet {
+                    ui.add(
+                        egui::DragValue::new(&mut self.pressure_noise_amplitude)
+                            .range(0.0..=0.3)
+                            .speed(0.01)
+                            .prefix(t!("pressure_noise")),
+                    );
                 }
-
-                for (index, point) in contour.points.iter().enumerate() {
-                    if let State::Stop = STATE.load() {
-                        break;
-                    }
-                    enigo
-                        .move_mouse(point.x, point.y, enigo::Coordinate::Abs)
-                        .ok();
-                    if index == 0 {
-                        enigo
-                            .button(enigo::Button::Left, enigo::Direction::Press)
-                            .ok();
-                    }
-                    thread::sleep(Duration::from_micros(100));
+                let selected = STROKE_KEYS
+                    .iter()
+                    .find(|(key, _)| Some(*key) == self.new_stroke_key)
+                    .map(|(_, name)| name.to_string())
+                    .unwrap_or_else(|| t!("none").to_string());
+                egui::ComboBox::from_label(t!("new_stroke_key"))
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.new_stroke_key, None, t!("none"));
+                        for (key, name) in STROKE_KEYS {
+                            ui.selectable_value(&mut self.new_stroke_key, Some(key), name);
+                        }
+                    });
+                ui.add(
+                    egui::DragValue::new(&mut self.point_delay_us)
+                        .range(0..=100_000)
+                        .suffix("µs")
+                        .prefix(t!("point_delay")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.contour_delay_ms)
+                        .range(0..=2_000)
+                        .suffix("ms")
+                        .prefix(t!("contour_delay")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.pen_travel_speed_px_per_us)
+                        .range(0.01..=1000.0)
+                        .speed(0.1)
+                        .suffix("px/µs")
+                        .prefix(t!("pen_travel_speed")),
+                )
+                .on_hover_text(t!("pen_travel_speed_hint"));
+                let mut long_press = self.long_press_threshold_ms.is_some();
+                if ui
+                    .checkbox(&mut long_press, t!("long_press"))
+                    .on_hover_text(t!("long_press_hint"))
+                    .changed()
+                {
+                    self.long_press_threshold_ms = long_press.then_some(500);
                 }
-                enigo
-                    .button(enigo::Button::Left, enigo::Direction::Release)
-                    .ok();
-                thread::sleep(Duration::from_millis(100));
-            }
-            STATE.store(State::Stop);
-            DRAWING.store(false);
-        });
-    }
-}
-
-impl App for Panel {
-    fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                if ui.button(t!("open_image")).clicked() {
-                    ctx.forget_all_images();
-                    self.open_image();
+                if let Some(hold) = &mut self.long_press_threshold_ms {
+                    ui.add(
+                        egui::DragValue::new(hold)
+                            .range(50..=5_000)
+                            .suffix("ms")
+                            .prefix(t!("long_press_hold")),
+                    );
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.speed_factor)
+                        .range(0.0..=10.0)
+                        .speed(0.1)
+                        .prefix(t!("speed_factor")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.corner_dwell_factor)
+                        .range(1.0..=10.0)
+                        .speed(0.1)
+                        .prefix(t!("corner_dwell")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.corner_threshold_deg)
+                        .range(0.0..=180.0)
+                        .suffix("°")
+                        .prefix(t!("corner_threshold")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.trail_skip_radius_px)
+                        .range(0..=20)
+                        .suffix("px")
+                        .prefix(t!("trail_skip_radius")),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.parallel_workers)
+                        .range(1..=4)
+                        .prefix(t!("parallel_workers")),
+                );
+                ui.checkbox(&mut self.grid_snap_first_point, t!("grid_snap"))
+                    .on_hover_text(t!("grid_snap_hint"));
+                if self.grid_snap_first_point {
+                    ui.label(t!("grid_origin"));
+                    ui.add(egui::DragValue::new(&mut self.grid_origin.0).prefix("x: "));
+                    ui.add(egui::DragValue::new(&mut self.grid_origin.1).prefix("y: "));
+                    ui.label(t!("grid_cell"));
+                    ui.add(
+                        egui::DragValue::new(&mut self.grid_cell.0)
+                            .range(1..=1000)
+                            .prefix("w: "),
+                    );
+                    ui.add(
+                        egui::DragValue::new(&mut self.grid_cell.1)
+                            .range(1..=1000)
+                            .prefix("h: "),
+                    );
                 }
+            });
+            let response = self
+                .section("speed_profile", t!("speed_profile"))
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        speed_profile_editor(ui, &mut self.speed_profile);
+                        if ui.button(t!("reset")).clicked() {
+                            self.speed_profile = SpeedProfile::default();
+                        }
+                    });
+                });
+            self.remember_section("speed_profile", &response);
+            ui.horizontal_wrapped(|ui| {
+                let previous = self.draw_mode;
                 if ui
-                    .selectable_value(&mut self.language, Language::Chinese, "简体中文")
+                    .selectable_label(self.draw_mode == DrawMode::Line, t!("mode_line"))
                     .clicked()
                 {
-                    rust_i18n::set_locale("zh-CN");
+                    self.draw_mode = DrawMode::Line;
                 }
                 if ui
-                    .selectable_value(&mut self.language, Language::English, "English")
+                    .selectable_label(
+                        matches!(self.draw_mode, DrawMode::Stipple { .. }),
+                        t!("mode_stipple"),
+                    )
                     .clicked()
+                    && !matches!(self.draw_mode, DrawMode::Stipple { .. })
                 {
-                    rust_i18n::set_locale("en-US");
+                    self.draw_mode = DrawMode::Stipple { min_spacing_px: 4 };
                 }
-            });
-            ui.separator();
-
-            ui.horizontal(|ui| {
                 if ui
-                    .add(
-                        egui::DragValue::new(&mut self.canny_value)
-                            .range(1..=u32::MAX)
-                            .prefix(t!("low_threshold")),
+                    .selectable_label(
+                        matches!(self.draw_mode, DrawMode::Stipple3D { .. }),
+                        t!("mode_stipple_3d"),
                     )
-                    .changed()
+                    .clicked()
+                    && !matches!(self.draw_mode, DrawMode::Stipple3D { .. })
                 {
-                    ctx.forget_all_images();
-                    self.reload(false);
+                    self.draw_mode = DrawMode::Stipple3D { min_spacing_px: 4 };
                 }
-                if ui
-                    .add(
-                        egui::DragValue::new(&mut self.area)
+                if let DrawMode::Stipple { min_spacing_px }
+                | DrawMode::Stipple3D { min_spacing_px } = &mut self.draw_mode
+                {
+                    ui.add(
+                        egui::DragValue::new(min_spacing_px)
                             .range(0..=100)
-                            .prefix(t!("draw_area"))
-                            .custom_formatter(|n, _| format!("{n}%")),
+                            .prefix(t!("min_spacing")),
+                    );
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::Spiral, t!("mode_spiral"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::Spiral;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::HilbertScan, t!("mode_hilbert"))
+                    .on_hover_text(t!("mode_hilbert_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::HilbertScan;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::Voronoi, t!("mode_voronoi"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::Voronoi;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::Contourf, t!("mode_contourf"))
+                    .on_hover_text(t!("mode_contourf_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::Contourf;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::Peano, t!("mode_peano"))
+                    .on_hover_text(t!("mode_peano_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::Peano;
+                }
+                if ui
+                    .selectable_label(
+                        self.draw_mode == DrawMode::ConcentricCircles,
+                        t!("mode_circles"),
                     )
-                    .changed()
+                    .on_hover_text(t!("mode_circles_hint"))
+                    .clicked()
                 {
-                    ctx.forget_all_images();
-                    self.reload(true);
+                    self.draw_mode = DrawMode::ConcentricCircles;
                 }
-                ui.add(
-                    egui::DragValue::new(&mut self.point_count)
-                        .range(0..=usize::MAX)
-                        .prefix(t!("pass_points")),
-                );
-                if ui.checkbox(&mut self.is_binary, t!("is_binary")).changed() {
+                if ui
+                    .selectable_label(
+                        self.draw_mode == DrawMode::ErrorDiffusionHalftone,
+                        t!("mode_halftone"),
+                    )
+                    .on_hover_text(t!("mode_halftone_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::ErrorDiffusionHalftone;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::StringArt, t!("mode_string_art"))
+                    .on_hover_text(t!("mode_string_art_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::StringArt;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::Truchet, t!("mode_truchet"))
+                    .on_hover_text(t!("mode_truchet_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::Truchet;
+                }
+                if ui
+                    .selectable_label(
+                        self.draw_mode == DrawMode::KochSnowflake,
+                        t!("mode_koch_snowflake"),
+                    )
+                    .on_hover_text(t!("mode_koch_snowflake_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::KochSnowflake;
+                }
+                if ui
+                    .selectable_label(self.draw_mode == DrawMode::Penrose, t!("mode_penrose"))
+                    .on_hover_text(t!("mode_penrose_hint"))
+                    .clicked()
+                {
+                    self.draw_mode = DrawMode::Penrose;
+                }
+                let is_3d = |mode: DrawMode| matches!(mode, DrawMode::Stipple3D { .. });
+                let mut changed = previous != self.draw_mode
+                    && (previous.is_generated()
+                        || self.draw_mode.is_generated()
+                        || is_3d(previous) != is_3d(self.draw_mode));
+                if self.draw_mode == DrawMode::Voronoi {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.voronoi_points)
+                                .range(100..=5000)
+                                .prefix(t!("voronoi_points")),
+                        )
+                        .changed();
+                }
+                let mut wavy = self.wave.is_some();
+                if ui.checkbox(&mut wavy, t!("wave")).changed() {
+                    self.wave = wavy.then(WaveDistort::default);
+                }
+                if let Some(wave) = &mut self.wave {
+                    ui.add(
+                        egui::Slider::new(&mut wave.amplitude, 0.0..=50.0)
+                            .suffix("px")
+                            .text(t!("wave_amplitude")),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut wave.frequency, 0.01..=0.5)
+                            .logarithmic(true)
+                            .text(t!("wave_frequency")),
+                    );
+                    ui.add(egui::Slider::new(&mut wave.phase, 0.0..=TAU).text(t!("wave_phase")));
+                }
+                if self.draw_mode == DrawMode::ConcentricCircles {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.max_radius_px)
+                                .range(10..=4000)
+                                .suffix("px")
+                                .prefix(t!("max_radius")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.ring_spacing_px)
+                                .range(1.0..=50.0)
+                                .speed(0.1)
+                                .suffix("px")
+                                .prefix(t!("ring_spacing")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.brightness_threshold)
+                                .range(1..=255)
+                                .prefix(t!("brightness_threshold")),
+                        )
+                        .changed();
+                }
+                if self.draw_mode == DrawMode::StringArt {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.peg_count)
+                                .range(50..=300)
+                                .prefix(t!("peg_count")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.string_count)
+                                .range(100..=5000)
+                                .prefix(t!("string_count")),
+                        )
+                        .changed();
+                }
+                if self.draw_mode == DrawMode::Truchet {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.tile_size_px)
+                                .range(10..=50)
+                                .suffix("px")
+                                .prefix(t!("tile_size")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut self.seed).prefix(t!("seed")))
+                        .changed();
+                }
+                if self.draw_mode == DrawMode::KochSnowflake {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.koch_iterations)
+                                .range(1..=6)
+                                .prefix(t!("koch_iterations")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.koch_size_px)
+                                .range(10.0..=4000.0)
+                                .suffix("px")
+                                .prefix(t!("koch_size")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.koch_rotation)
+                                .range(0.0..=360.0)
+                                .suffix("°")
+                                .prefix(t!("koch_rotation")),
+                        )
+                        .changed();
+                }
+                if self.draw_mode == DrawMode::Penrose {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.penrose_iterations)
+                                .range(3..=7)
+                                .prefix(t!("penrose_iterations")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.penrose_scale)
+                                .range(1.0..=4.0)
+                                .speed(0.05)
+                                .prefix(t!("penrose_scale")),
+                        )
+                        .changed();
+                }
+                if self.draw_mode == DrawMode::Peano {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.sampling_rate)
+                                .range(1..=16)
+                                .prefix(t!("sampling_rate")),
+                        )
+                        .changed();
+                }
+                if self.draw_mode == DrawMode::Contourf {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.contourf_levels)
+                                .range(4..=16)
+                                .prefix(t!("contourf_levels")),
+                        )
+                        .changed();
+                }
+                if is_3d(self.draw_mode) {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.depth_scale)
+                                .range(0.1..=20.0)
+                                .speed(0.1)
+                                .prefix(t!("depth_scale")),
+                        )
+                        .changed();
+                    ui.add(
+                        egui::DragValue::new(&mut self.max_dwell_ms)
+                            .range(0..=2000)
+                            .suffix("ms")
+                            .prefix(t!("max_dwell")),
+                    );
+                }
+                if self.draw_mode == DrawMode::Spiral {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.spiral_turns)
+                                .range(1..=500)
+                                .prefix(t!("spiral_turns")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.spiral_spacing)
+                                .range(1.0..=50.0)
+                                .speed(0.1)
+                                .prefix(t!("spiral_spacing")),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.darkness_threshold)
+                                .range(1..=255)
+                                .prefix(t!("darkness_threshold")),
+                        )
+                        .changed();
+                }
+                if !self.draw_mode.is_generated() {
+                    changed |= ui.checkbox(&mut self.hatch, t!("hatch")).changed();
+                    if self.hatch {
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.hatch_angle)
+                                    .range(0.0..=180.0)
+                                    .suffix("°")
+                                    .prefix(t!("hatch_angle")),
+                            )
+                            .changed();
+                        changed |= ui
+                            .add(
+                                egui::DragValue::new(&mut self.hatch_spacing)
+                                    .range(2.0..=100.0)
+                                    .speed(0.1)
+                                    .prefix(t!("hatch_spacing")),
+                            )
+                            .changed();
+                    }
+                }
+                changed |= ui
+                    .checkbox(&mut self.palette_mapping, t!("palette_mapping"))
+                    .on_hover_text(t!("palette_mapping_hint"))
+                    .changed();
+                if self.palette_mapping {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut self.palette_size)
+                                .range(MIN_PALETTE..=MAX_PALETTE)
+                                .prefix(t!("palette_size")),
+                        )
+                        .changed();
+                    ui.horizontal(|ui| {
+                        ui.label(t!("color_picker_shortcut"));
+                        ui.text_edit_singleline(&mut self.color_picker_shortcut)
+                            .on_hover_text(t!("color_picker_shortcut_hint"));
+                    });
+                    if let Some(palette) = self.palette.write().as_mut() {
+                        palette_order_editor(ui, palette);
+                    }
+                }
+                if self.draw_mode == DrawMode::Line {
+                    let response = self
+                        .section("passes", t!("passes"))
+                        .show(ui, |ui| self.passes_ui(ui));
+                    self.remember_section("passes", &response);
+                }
+                if changed {
                     ctx.forget_all_images();
                     self.reload(false);
                 }
             });
             ui.separator();
 
-            ui.label(t!("start"));
-            ui.label(t!("stop"));
+            ui.label(t!("start", key = key_name(self.hotkeys.start)));
+            ui.label(t!("stop", key = key_name(self.hotkeys.stop)));
+            ui.label(t!("pause", key = key_name(self.hotkeys.pause)));
+            ui.label(t!("skip", key = key_name(self.hotkeys.skip)));
+            ui.label(t!("canny_keys"));
+            let response = self
+                .section("configure_hotkeys", t!("configure_hotkeys"))
+                .show(ui, |ui| {
+                    for hotkey in Hotkey::ALL {
+                        ui.horizontal(|ui| {
+                            ui.label(hotkey_label(hotkey));
+                            let text = if self.rebinding == Some(hotkey) {
+                                t!("press_a_key").to_string()
+                            } else {
+                                key_name(self.hotkeys.get(hotkey))
+                            };
+                            if ui.button(text).clicked() {
+                                self.rebinding = Some(hotkey);
+                            }
+                        });
+                    }
+                });
+            self.remember_section("configure_hotkeys", &response);
+            ui.horizontal(|ui| {
+                if ui.button(t!("calibrate_warp")).clicked() {
+                    self.start_calibration();
+                }
+                if ui
+                    .add_enabled(
+                        self.homography.is_some(),
+                        egui::Button::new(t!("clear_warp")),
+                    )
+                    .clicked()
+                {
+                    self.homography = None;
+                }
+                if self.calibrating && self.warp_source.len() < 4 {
+                    ui.colored_label(
+                        CANVAS_COLOR,
+                        t!("warp_source", value = self.warp_source.len() + 1),
+                    );
+                }
+            });
+            if self.awaiting_confirmation.load() {
+                ui.colored_label(
+                    HIGHLIGHT_COLOR,
+                    t!("thumbnail_confirm", key = key_name(self.hotkeys.stop)),
+                );
+            }
+            let invalid = self.invalid_point_count.load();
+            if invalid > 0 {
+                ui.colored_label(OVERLAY_COLOR, t!("invalid_points", value = invalid));
+            }
+            if self.dpi_scaled || self.fix_dpi {
+                ui.horizontal_wrapped(|ui| {
+                    if self.dpi_scaled {
+                        ui.colored_label(OVERLAY_COLOR, t!("dpi_scaled"));
+                    }
+                    if ui
+                        .checkbox(&mut self.fix_dpi, t!("fix_dpi"))
+                        .on_hover_text(t!("fix_dpi_hint"))
+                        .changed()
+                    {
+                        let mut settings = load_settings();
+                        settings.fix_dpi = self.fix_dpi;
+                        settings.save().ok();
+                    }
+                });
+            }
+            let total = self.total_points.load();
+            if let Some(started) = self
+                .draw_started
+                .load()
+                .filter(|_| DRAWING.load() && total > 0)
+            {
+                let progress = (self.drawn_points.load() as f32 / total as f32).min(1.0);
+                ui.add(egui::ProgressBar::new(progress).show_percentage());
+                if progress > 0.0 {
+                    let remaining = started.elapsed().as_secs_f32() * (1.0 - progress) / progress;
+                    ui.label(t!(
+                        "eta",
+                        minutes = remaining as u32 / 60,
+                        seconds = remaining as u32 % 60
+                    ));
+                }
+            }
+            if STATE.load() == State::Paused {
+                ui.colored_label(
+                    HIGHLIGHT_COLOR,
+                    t!("paused", key = key_name(self.hotkeys.pause)),
+                );
+            }
+            if let Some((chunk, left)) = self.chunk_countdown.load() {
+                ui.colored_label(
+                    HIGHLIGHT_COLOR,
+                    t!(
+                        "chunk_countdown",
+                        chunk = chunk + 1,
+                        total = self.chunks,
+                        value = left
+                    ),
+                );
+            }
             ui.separator();
 
-            if let Some(image) = self.canny_image.read().as_ref() {
-                ui.add(Image::from_bytes(image.id.to_string(), image.buf.to_vec()));
+            let response = self
+                .section("layers", t!("layers"))
+                .show(ui, |ui| self.layers_ui(ctx, ui));
+            self.remember_section("layers", &response);
+
+            let key = self.measure_key();
+            if self.measured_with.as_ref() != Some(&key) {
+                self.measured_with = Some(key);
+                self.measure();
+            }
+            let measures = self.measures.read().clone();
+            if self.lines.read().is_some() {
+                let stopped_at = self.start_from.load();
+                if stopped_at > 0 {
+                    ui.colored_label(
+                        HIGHLIGHT_COLOR,
+                        t!(
+                            "stopped_at",
+                            value = stopped_at,
+                            total = measures.drawable,
+                            key = key_name(self.hotkeys.start)
+                        ),
+                    );
+                }
+                ui.label(t!(
+                    "drawing_cost",
+                    stroke = format!("{:.0}", measures.cost.stroke_mm),
+                    travel = format!("{:.0}", measures.cost.travel_mm)
+                ));
+            }
+            if let Some(overlap) = measures.overlap {
+                ui.label(t!("stroke_overlap", value = format!("{overlap:.1}")))
+                    .on_hover_text(t!("stroke_overlap_hint"));
+            }
+
+            let stats = self.stroke_stats.read().clone();
+            // Pass strokes can't be looked up in `lines` to map or highlight.
+            if !stats.is_empty() && !self.stats_from_passes.load() {
+                let response = self.section("draw_stats", t!("draw_stats")).show(ui, |ui| {
+                    ui.horizontal_top(|ui| {
+                        self.paint_drawing_map(ui, &stats);
+                        ui.vertical(|ui| {
+                            stats_table(ui, &stats, &mut self.stats_sort, &mut self.highlight);
+                        });
+                    });
+                });
+                self.remember_section("draw_stats", &response);
+            }
+
+            if self.show_density {
+                if let Some((_, mean, peak)) = self.density.read().as_ref() {
+                    ui.label(t!(
+                        "density_stats",
+                        mean = format!("{mean:.1}"),
+                        peak = peak
+                    ));
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.show_simplified, t!("show_simplified"));
+                let before = self.simplification_epsilon;
+                // Resimplifying would block on a draw that is tracing them.
+                let response = ui.add_enabled(
+                    !DRAWING.load(),
+                    egui::DragValue::new(&mut self.simplification_epsilon)
+                        .range(0.0..=20.0)
+                        .speed(0.1)
+                        .prefix(t!("simplify_epsilon")),
+                );
+                self.record_change(&response, Param::Simplify(before));
+                if response.changed() {
+                    self.resimplify();
+                }
+            });
+
+            let coverage = measures.coverage;
+            if let Some(cells) = &coverage {
+                ui.label(t!(
+                    "coverage_uniformity",
+                    value = format!("{:.2}", 1.0 - gini(cells))
+                ));
+            }
+
+            let mut source_click = None;
+            let preview = self
+                .canny_image
+                .read()
+                .as_ref()
+                .map(|image| Image::from_bytes(image.id.to_string(), image.buf.to_vec()));
+            if let Some(preview) = preview {
+                // Cross-fades between the full edge image and the simplified contours.
+                let fade = ctx.animate_bool_with_time(
+                    egui::Id::new("show_simplified"),
+                    self.show_simplified,
+                    0.3,
+                );
+                let preview = preview.tint(egui::Color32::WHITE.gamma_multiply(1.0 - fade));
+                let image_size = preview
+                    .load_and_calc_size(ui, egui::Vec2::INFINITY)
+                    .unwrap_or_default();
+                let size = image_size * self.preview_zoom;
+                let preview = preview.fit_to_exact_size(size);
+                // Half the window at most, so the controls below stay in reach.
+                let output = egui::ScrollArea::both()
+                    .id_salt("preview")
+                    .max_height(ctx.screen_rect().height() / 2.0)
+                    .scroll_offset(self.preview_offset)
+                    .drag_to_scroll(false)
+                    .show(ui, |ui| {
+                        let (view, response) =
+                            ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+                        let offset = self.zoom_preview(ui, &response);
+                        self.zoom_and_pan(ui, &response);
+                        // Everything is painted into the zoomed rect and clipped to the view.
+                        let rect = egui::Rect::from_min_size(
+                            view.min + self.pan_offset,
+                            size * self.zoom_scale,
+                        );
+                        let clip = ui.clip_rect();
+                        ui.set_clip_rect(view.intersect(clip));
+                        preview.paint_at(ui, rect);
+                        if self.calibrating && response.clicked() {
+                            source_click = response.interact_pointer_pos().map(|pos| (pos, rect));
+                        }
+                        self.paint_markers(ui, rect, &self.warp_source);
+                        if fade > 0.0 {
+                            self.paint_simplified(ui, rect, fade);
+                        }
+                        if self.show_density {
+                            if let Some((heatmap, ..)) = self.density.read().as_ref() {
+                                Image::from_bytes(heatmap.id.to_string(), heatmap.buf.to_vec())
+                                    .paint_at(ui, rect);
+                            }
+                        }
+                        if let Some(cells) = &coverage {
+                            self.paint_coverage(ui, rect, cells);
+                        }
+                        if let Some(index) = self.highlight {
+                            self.paint_contour(ui, rect, index, HIGHLIGHT_COLOR);
+                        }
+                        ui.set_clip_rect(clip);
+                        offset
+                    });
+                self.preview_offset = output.inner.unwrap_or(output.state.offset);
+            }
+
+            if let Some((pos, rect)) = source_click.filter(|_| self.warp_source.len() < 4) {
+                if let Some(point) = self.screen_point(rect, pos) {
+                    self.warp_source.push(point);
+                }
             }
 
-            if is_pressed(VK_F1.0) && matches!(STATE.load(), State::Stop) && !DRAWING.load() {
-                self.draw();
+            if self.just_pressed(self.hotkeys.start)
+                && matches!(STATE.load(), State::Stop)
+                && !DRAWING.load()
+            {
+                self.draw(ctx);
             }
-            if is_pressed(VK_F2.0) {
+            if is_pressed(self.hotkeys.stop) {
                 STATE.store(State::Stop);
             }
+            // Reloading swaps out the contours, which would block on a running
+            // draw. Pause and skip are up to the draw's own key watcher.
+            let idle = !DRAWING.load();
+            if idle && self.just_pressed(VK_F3.0) {
+                self.step_canny(ctx, true);
+            }
+            if idle && self.just_pressed(VK_F4.0) {
+                self.step_canny(ctx, false);
+            }
+            if let Some(hotkey) = self.rebinding {
+                self.rebind(hotkey);
+                ctx.request_repaint();
+            }
+
+            if idle && ctx.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::Z)) {
+                self.undo(ctx);
+            }
+            if idle && ctx.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::Y)) {
+                self.redo(ctx);
+            }
+
+            let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+            if !dropped.is_empty() {
+                self.open_dropped(ctx, &dropped);
+            }
 
             if ctx.input(|i| i.modifiers.ctrl && i.key_released(egui::Key::V)) {
                 let Some(raw_image) = load_image_from_clipboard().ok() else {
                     return;
                 };
+                self.layers.write().clear();
+                self.image_path.write().take();
+                self.pre_crop = None;
                 self.raw_img.write().replace(raw_image);
+                self.is_mask.store(false);
                 ctx.forget_all_images();
                 self.reload(true);
             }
         });
+
+        let detected = self.detected_canvas.write().take();
+        if let Some(rect) = detected {
+            self.apply_canvas(ctx, rect);
+        }
+
+        if self.show_overlay {
+            self.show_overlay(ctx);
+        }
+        if self.calibrating && self.warp_source.len() == 4 {
+            self.show_calibration(ctx);
+        }
+
+        if let Some((text, shown)) = &self.toast {
+            if shown.elapsed() < TOAST_DURATION {
+                egui::Area::new(egui::Id::new("toast"))
+                    .anchor(egui::Align2::CENTER_BOTTOM, [0.0, -20.0])
+                    .show(ctx, |ui| {
+                        egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(text.as_str()));
+                    });
+            } else {
+                self.toast = None;
+            }
+        }
+    }
+}
+
+/// Splits contours by the screen quadrant of their first point, handing the
+/// quadrants out round-robin to `workers` groups.
+fn split_quadrants<'a>(
+    contours: &[(usize, &'a Contour<i32>)],
+    workers: u8,
+) -> Vec<Vec<(usize, &'a Contour<i32>)>> {
+    let workers = workers.clamp(1, 4) as usize;
+    let mut groups = vec![vec![]; workers];
+    if workers == 1 {
+        groups[0] = contours.to_vec();
+        return groups;
+    }
+
+    let points = contours
+        .iter()
+        .flat_map(|(_, contour)| contour.points.iter());
+    let (min_x, max_x) = points.clone().fold((i32::MAX, i32::MIN), |(lo, hi), p| {
+        (lo.min(p.x), hi.max(p.x))
+    });
+    let (min_y, max_y) = points.fold((i32::MAX, i32::MIN), |(lo, hi), p| {
+        (lo.min(p.y), hi.max(p.y))
+    });
+    let mid = ((min_x + max_x) / 2, (min_y + max_y) / 2);
+
+    for &(index, contour) in contours {
+        let Some(first) = contour.points.first() else {
+            continue;
+        };
+        let quadrant = (first.x >= mid.0) as usize + 2 * (first.y >= mid.1) as usize;
+        groups[quadrant % workers].push((index, contour));
+    }
+    groups
+}
+
+fn pen_up_label(style: PenUpStyle) -> String {
+    match style {
+        PenUpStyle::Direct => t!("pen_up_direct"),
+        PenUpStyle::Arc => t!("pen_up_arc"),
+        PenUpStyle::RandomOffset => t!("pen_up_random"),
+    }
+    .to_string()
+}
+
+fn hotkey_label(hotkey: Hotkey) -> String {
+    match hotkey {
+        Hotkey::Start => t!("hotkey_start"),
+        Hotkey::Stop => t!("hotkey_stop"),
+        Hotkey::Pause => t!("hotkey_pause"),
+        Hotkey::Skip => t!("hotkey_skip"),
+    }
+    .to_string()
+}
+
+fn window_level(always_on_top: bool) -> egui::WindowLevel {
+    if always_on_top {
+        egui::WindowLevel::AlwaysOnTop
+    } else {
+        egui::WindowLevel::Normal
+    }
+}
+
+fn set_locale(language: Language) {
+    rust_i18n::set_locale(match language {
+        Language::Chinese => "zh-CN",
+        Language::English => "en-US",
+    });
+}
+
+fn resize_mode_label(mode: ResizeMode) -> String {
+    match mode {
+        ResizeMode::Fit => t!("resize_fit"),
+        ResizeMode::Fill => t!("resize_fill"),
+        ResizeMode::Stretch => t!("resize_stretch"),
+    }
+    .to_string()
+}
+
+fn resize_filter_label(filter: ResizeFilter) -> String {
+    match filter {
+        ResizeFilter::Nearest => t!("filter_nearest"),
+        ResizeFilter::Triangle => t!("filter_triangle"),
+        ResizeFilter::CatmullRom => t!("filter_catmull_rom"),
+        ResizeFilter::Gaussian => t!("filter_gaussian"),
+        ResizeFilter::Lanczos3 => t!("filter_lanczos3"),
+    }
+    .to_string()
+}
+
+fn edge_method_label(method: EdgeMethod) -> String {
+    match method {
+        EdgeMethod::Canny => t!("edge_canny"),
+        EdgeMethod::Sobel => t!("edge_sobel"),
+        EdgeMethod::Laplacian => t!("edge_laplacian"),
+    }
+    .to_string()
+}
+
+fn draw_order_label(order: DrawOrder) -> String {
+    match order {
+        DrawOrder::Original => t!("order_original"),
+        DrawOrder::Importance => t!("order_importance"),
+        DrawOrder::BottomToTop => t!("order_bottom_to_top"),
+        DrawOrder::TopToBottom => t!("order_top_to_bottom"),
+        DrawOrder::LeftToRight => t!("order_left_to_right"),
+        DrawOrder::RightToLeft => t!("order_right_to_left"),
+    }
+    .to_string()
+}
+
+/// Outlines `rect` `width` times, each loop one pixel further in, sampled
+/// every pixel.
+fn border_contours(rect: ScreenRect, width: u8) -> Vec<Contour<i32>> {
+    (0..width as i32)
+        .filter_map(|inset| {
+            let (left, top) = (rect.x + inset, rect.y + inset);
+            let right = rect.x + rect.width as i32 - 1 - inset;
+            let bottom = rect.y + rect.height as i32 - 1 - inset;
+            if right <= left || bottom <= top {
+                return None;
+            }
+            let points = (left..right)
+                .map(|x| Point::new(x, top))
+                .chain((top..bottom).map(|y| Point::new(right, y)))
+                .chain((left + 1..=right).rev().map(|x| Point::new(x, bottom)))
+                .chain((top..=bottom).rev().map(|y| Point::new(left, y)))
+                .collect();
+            Some(Contour::new(
+                points,
+                imageproc::contours::BorderType::Outer,
+                None,
+            ))
+        })
+        .collect()
+}
+
+/// Shrinks the drawing to half size around `center`, keeping only points at
+/// least `THUMBNAIL_SPACING` apart so the proof goes down quickly.
+fn thumbnail_contours(
+    contours: &[(usize, &Contour<i32>)],
+    center: Point<i32>,
+) -> Vec<Contour<i32>> {
+    contours
+        .iter()
+        .map(|(_, contour)| {
+            let mut points: Vec<Point<i32>> = vec![];
+            for point in &contour.points {
+                let point = Point::new(
+                    center.x + (point.x - center.x) / 2,
+                    center.y + (point.y - center.y) / 2,
+                );
+                if points
+                    .last()
+                    .is_none_or(|last| distance(last, &point) >= THUMBNAIL_SPACING)
+                {
+                    points.push(point);
+                }
+            }
+            Contour::new(points, contour.border_type, contour.parent)
+        })
+        .collect()
+}
+
+fn distance(a: &Point<i32>, b: &Point<i32>) -> f32 {
+    (((a.x - b.x).pow(2) + (a.y - b.y).pow(2)) as f32).sqrt()
+}
+
+/// Moves the cursor to `target` and reads it back, catching setups where
+/// injected input is silently dropped (e.g. an elevated target window).
+fn verify_injection(enigo: &mut Enigo, target: Point<i32>) -> bool {
+    enigo
+        .move_mouse(target.x, target.y, enigo::Coordinate::Abs)
+        .ok();
+    thread::sleep(Duration::from_millis(50));
+    let Some(actual) = cursor_pos() else {
+        return false;
+    };
+    distance(&actual, &target) <= INJECTION_TOLERANCE
+}
+
+/// Clicks a test mark at `at` and finds where it actually appeared, then
+/// undoes it. `None` when nothing showed up near the spot.
+fn sample_mark(input: &mut Input, at: Point<i32>) -> Option<Point<i32>> {
+    let region = ScreenRect {
+        x: at.x - PROBE_RADIUS,
+        y: at.y - PROBE_RADIUS,
+        width: PROBE_RADIUS as u32 * 2 + 1,
+        height: PROBE_RADIUS as u32 * 2 + 1,
+    };
+    // The cursor moves there first so hover effects are in both captures.
+    input.move_to(&at, 0.0);
+    thread::sleep(PROBE_DELAY);
+    let before = capture_screen(region)?;
+    input.click(1.0);
+    thread::sleep(PROBE_DELAY);
+    let after = capture_screen(region);
+    input.chord(&[VK_CONTROL.0, VK_Z.0]);
+    thread::sleep(PROBE_DELAY);
+    let (x, y) = find_mark(&before, &after?)?;
+    Some(Point::new(
+        region.x + x.round() as i32,
+        region.y + y.round() as i32,
+    ))
+}
+
+/// Waits for a left-button drag and returns the rectangle it spans, clamped
+/// to the screen. `None` on Esc or when the drag is too small to use.
+#[cfg(target_os = "windows")]
+fn select_region() -> Option<ScreenRect> {
+    let wait_for = |down: bool| {
+        while is_pressed(VK_LBUTTON.0) != down {
+            if is_pressed(VK_ESCAPE.0) {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        true
+    };
+    // Let go of the button that started this first.
+    if !wait_for(false) || !wait_for(true) {
+        return None;
     }
+    let start = cursor_pos()?;
+    if !wait_for(false) {
+        return None;
+    }
+    let end = cursor_pos()?;
+    let (left, right) = (start.x.min(end.x), start.x.max(end.x));
+    let (top, bottom) = (start.y.min(end.y), start.y.max(end.y));
+    let (left, top) = (left.clamp(0, SCREEN.0), top.clamp(0, SCREEN.1));
+    let (right, bottom) = (right.clamp(0, SCREEN.0), bottom.clamp(0, SCREEN.1));
+    (right - left > 1 && bottom - top > 1).then(|| ScreenRect {
+        x: left,
+        y: top,
+        width: (right - left) as u32,
+        height: (bottom - top) as u32,
+    })
+}
+
+pub fn cursor_pos() -> Option<Point<i32>> {
+    let mut point = POINT::default();
+    unsafe { GetCursorPos(&mut point) }.ok()?;
+    Some(Point::new(point.x, point.y))
 }
 
 pub fn is_pressed(vk: u16) -> bool {
@@ -391,6 +4908,64 @@ pub fn is_pressed(vk: u16) -> bool {
     status >> 31 == 1
 }
 
+/// Pauses a running draw, or resumes a paused one.
+fn toggle_pause() {
+    if STATE
+        .compare_exchange(State::Drawing, State::Paused)
+        .is_err()
+    {
+        let _ = STATE.compare_exchange(State::Paused, State::Drawing);
+    }
+}
+
+/// Blocks while the draw is paused; `false` once it has been stopped.
+fn wait_while_paused() -> bool {
+    while STATE.load() == State::Paused {
+        thread::sleep(PAUSE_POLL);
+    }
+    STATE.load() != State::Stop
+}
+
+/// Moves screen-space contours back onto the image they came from.
+fn image_space<'a>(
+    contours: impl IntoIterator<Item = &'a Contour<i32>>,
+    center: (i32, i32),
+) -> Vec<Contour<i32>> {
+    contours
+        .into_iter()
+        .map(|contour| {
+            Contour::new(
+                contour
+                    .points
+                    .iter()
+                    .map(|p| Point::new(p.x - center.0, p.y - center.1))
+                    .collect(),
+                contour.border_type,
+                contour.parent,
+            )
+        })
+        .collect()
+}
+
+fn pick_image() -> Option<(PathBuf, DynamicImage)> {
+    let path = FileDialog::new()
+        .add_filter("Image file", IMAGE_EXTENSIONS)
+        .pick_file()?;
+    let image = read_image(&path)?;
+    Some((path, image))
+}
+
+fn read_image(path: &Path) -> Option<DynamicImage> {
+    let Ok(image) = image::open(path) else {
+        rfd::MessageDialog::new()
+            .set_title("Error")
+            .set_description("No image")
+            .show();
+        return None;
+    };
+    Some(image)
+}
+
 fn load_image_from_clipboard() -> Result<DynamicImage, Box<dyn Error>> {
     let mut clipboard = Clipboard::new()?;
     let image = clipboard.get_image()?;
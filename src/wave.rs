@@ -0,0 +1,41 @@
+use std::f32::consts::TAU;
+
+use imageproc::{contours::Contour, point::Point};
+
+/// Sine displacement of each point along y, for a hand-wobbled look.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveDistort {
+    /// Peak displacement in pixels.
+    pub amplitude: f32,
+    /// Cycles per pixel along x.
+    pub frequency: f32,
+    /// Radians.
+    pub phase: f32,
+}
+
+impl Default for WaveDistort {
+    fn default() -> Self {
+        Self {
+            amplitude: 10.0,
+            frequency: 0.05,
+            phase: 0.0,
+        }
+    }
+}
+
+impl WaveDistort {
+    /// `origin_x` is where the wave starts, so it stays put on the image
+    /// wherever that is drawn.
+    pub fn apply(&self, contour: &Contour<i32>, origin_x: i32) -> Contour<i32> {
+        let points = contour
+            .points
+            .iter()
+            .map(|p| {
+                let x = (p.x - origin_x) as f32;
+                let offset = self.amplitude * (TAU * self.frequency * x + self.phase).sin();
+                Point::new(p.x, p.y + offset.round() as i32)
+            })
+            .collect();
+        Contour::new(points, contour.border_type, contour.parent)
+    }
+}
@@ -0,0 +1,30 @@
+use windows::Win32::{
+    Foundation::{BOOL, HWND, LPARAM},
+    UI::WindowsAndMessaging::{EnumWindows, GetWindowTextW, IsWindowVisible},
+};
+
+/// Titles of the visible top-level windows, in z-order. Untitled windows
+/// are left out.
+pub fn window_titles() -> Vec<String> {
+    let mut titles: Vec<String> = vec![];
+    unsafe {
+        EnumWindows(
+            Some(collect_title),
+            LPARAM(&mut titles as *mut Vec<String> as isize),
+        )
+        .ok();
+    }
+    titles
+}
+
+unsafe extern "system" fn collect_title(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let titles = &mut *(lparam.0 as *mut Vec<String>);
+    if IsWindowVisible(hwnd).as_bool() {
+        let mut buffer = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut buffer);
+        if len > 0 {
+            titles.push(String::from_utf16_lossy(&buffer[..len as usize]));
+        }
+    }
+    true.into()
+}
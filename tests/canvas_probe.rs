@@ -0,0 +1,54 @@
+// Only the probe geometry is exercised; the GDI capture needs a desktop.
+#[allow(dead_code)]
+#[path = "../src/capture.rs"]
+mod capture;
+
+use capture::{canvas_from_marks, probe_points, ScreenRect};
+
+fn round_trip(area: ScreenRect) {
+    let probes = probe_points(area);
+    let marks = probes.map(Some);
+    let (canvas, scale) = canvas_from_marks(&probes, &marks).unwrap();
+    assert!((canvas.x - area.x).abs() <= 1, "{canvas:?} from {area:?}");
+    assert!((canvas.y - area.y).abs() <= 1, "{canvas:?} from {area:?}");
+    assert!(
+        canvas.width.abs_diff(area.width) <= 1,
+        "{canvas:?} from {area:?}"
+    );
+    assert!(
+        canvas.height.abs_diff(area.height) <= 1,
+        "{canvas:?} from {area:?}"
+    );
+    assert_eq!(scale, 1.0);
+}
+
+#[test]
+fn marks_on_target_give_back_the_probed_area() {
+    round_trip(ScreenRect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    });
+    round_trip(ScreenRect {
+        x: 317,
+        y: 45,
+        width: 1001,
+        height: 643,
+    });
+}
+
+#[test]
+fn probing_the_detected_canvas_again_keeps_its_size() {
+    let area = ScreenRect {
+        x: 200,
+        y: 100,
+        width: 800,
+        height: 600,
+    };
+    let probes = probe_points(area);
+    let (first, _) = canvas_from_marks(&probes, &probes.map(Some)).unwrap();
+    let probes = probe_points(first);
+    let (second, _) = canvas_from_marks(&probes, &probes.map(Some)).unwrap();
+    assert_eq!(first, second);
+}
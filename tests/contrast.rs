@@ -0,0 +1,39 @@
+#[path = "../src/contrast.rs"]
+mod contrast;
+
+use contrast::{adjust_contrast, contrast_gain};
+use image::{GrayImage, Luma};
+
+fn ramp() -> GrayImage {
+    GrayImage::from_fn(256, 1, |x, _| Luma([x as u8]))
+}
+
+fn spread(gray: &GrayImage) -> u8 {
+    let (min, max) = gray.pixels().fold((u8::MAX, u8::MIN), |(min, max), p| {
+        (min.min(p.0[0]), max.max(p.0[0]))
+    });
+    max - min
+}
+
+#[test]
+fn lower_contrast_never_widens_the_spread() {
+    let ramp = ramp();
+    let mut previous = 0;
+    for step in 0..=40 {
+        let contrast = -1.0 + step as f32 * 0.1;
+        let spread = spread(&adjust_contrast(&ramp, contrast));
+        assert!(
+            spread >= previous,
+            "contrast {contrast} spread {spread} below {previous}"
+        );
+        previous = spread;
+    }
+}
+
+#[test]
+fn one_leaves_the_image_and_minus_one_flattens_it() {
+    let ramp = ramp();
+    assert_eq!(adjust_contrast(&ramp, 1.0), ramp);
+    assert_eq!(spread(&adjust_contrast(&ramp, -1.0)), 0);
+    assert!(contrast_gain(0.0) < contrast_gain(1.0));
+}
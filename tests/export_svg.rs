@@ -0,0 +1,34 @@
+#[path = "../src/export.rs"]
+mod export;
+
+use imageproc::{
+    contours::{BorderType, Contour},
+    point::Point,
+};
+
+fn contour(points: &[(i32, i32)]) -> Contour<i32> {
+    Contour::new(
+        points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+        BorderType::Outer,
+        None,
+    )
+}
+
+#[test]
+fn writes_one_polyline_per_contour() {
+    let contours = [
+        contour(&[(0, 0), (10, 0), (10, 10)]),
+        contour(&[(5, 5)]),
+        contour(&[]),
+    ];
+    let path = std::env::temp_dir().join("auto-draw-export-test.svg");
+    export::export_svg(&contours, 20, 20, &path).unwrap();
+    let svg = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains("viewBox=\"0 0 20 20\""));
+    assert_eq!(svg.matches("<polyline").count(), 2);
+    assert!(svg.contains("<polyline points=\"0,0 10,0 10,10\"/>"));
+    assert!(svg.contains("<polyline points=\"5,5 5,5\"/>"));
+}
@@ -0,0 +1,51 @@
+#[path = "../src/simplify.rs"]
+mod simplify;
+
+use imageproc::{
+    contours::{BorderType, Contour},
+    point::Point,
+};
+use simplify::simplify_contour;
+
+fn contour(points: &[(i32, i32)]) -> Contour<i32> {
+    Contour::new(
+        points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+        BorderType::Outer,
+        None,
+    )
+}
+
+fn points(points: &[(i32, i32)]) -> Vec<Point<i32>> {
+    points.iter().map(|&(x, y)| Point::new(x, y)).collect()
+}
+
+#[test]
+fn collinear_points_collapse_to_the_ends() {
+    let line = contour(&[(0, 0), (1, 1), (2, 2), (5, 5), (9, 9)]);
+    assert_eq!(simplify_contour(&line, 0.5), points(&[(0, 0), (9, 9)]));
+}
+
+#[test]
+fn zero_epsilon_keeps_every_point() {
+    let zigzag = contour(&[(0, 0), (1, 1), (2, 2), (3, 0), (4, 4)]);
+    assert_eq!(simplify_contour(&zigzag, 0.0), zigzag.points);
+}
+
+#[test]
+fn closed_contour_keeps_its_corners() {
+    let square = contour(&[
+        (0, 0),
+        (5, 0),
+        (10, 0),
+        (10, 5),
+        (10, 10),
+        (5, 10),
+        (0, 10),
+        (0, 5),
+        (0, 0),
+    ]);
+    assert_eq!(
+        simplify_contour(&square, 1.0),
+        points(&[(0, 0), (10, 0), (10, 10), (0, 10), (0, 0)])
+    );
+}